@@ -1,3 +1,38 @@
 #!/bin/bash
-cargo web deploy --release
-sed -i "s/{{ver}}/$(sha1sum target/deploy/xjtlu-timetable.js | cut -d " " -f 1)/g" target/deploy/index.html
\ No newline at end of file
+set -e
+OUT=target/deploy
+BOOTSTRAP_CSS=https://maxcdn.bootstrapcdn.com/bootstrap/4.0.0/css/bootstrap.min.css
+BOOTSTRAP_CSS_SHA384=Gn5384xqQ1aoWXA+058RXPxPg6fy4IWvTNh0E263XmFcJlSAwiGgFAW/dAiS6JXm
+
+cargo build --release --target wasm32-unknown-unknown --bin xjtlu-timetable
+wasm-bindgen --target web --no-typescript --out-dir $OUT target/wasm32-unknown-unknown/release/xjtlu-timetable.wasm
+cp static/* $OUT/
+
+# Inline the stylesheet, so that the page does not depend on any CDN
+# The download is kept across builds and checked against its known hash
+CSS=target/bootstrap.min.css
+if [ ! -f $CSS ]; then
+    curl -fsSL -o $CSS.tmp $BOOTSTRAP_CSS
+    mv $CSS.tmp $CSS
+fi
+if [ "$(openssl dgst -sha384 -binary $CSS | base64 -w 0)" != "$BOOTSTRAP_CSS_SHA384" ]; then
+    echo "$CSS does not match its hash" >&2
+    rm -f $CSS
+    exit 1
+fi
+awk -v css=$CSS '
+    /<!-- Replaced by the stylesheet itself/ { next }
+    /<link rel="stylesheet"/ {
+        print "    <style>"
+        while ((getline line < css) > 0) {
+            if (line !~ /sourceMappingURL/) print line
+        }
+        print "    </style>"
+        next
+    }
+    { print }
+' static/index.html > $OUT/index.html
+
+# A new version of the app gets a new cache in the service worker
+VER=$(cat $OUT/xjtlu-timetable.js $OUT/xjtlu-timetable_bg.wasm $OUT/index.html | sha1sum | cut -d " " -f 1)
+sed -i "s/{{ver}}/$VER/g" $OUT/index.html $OUT/sw.js
@@ -1,13 +1,28 @@
 use base64::encode;
-use parser::parse;
-use ical::{self, ICalElement};
+use xjtlu_timetable::class::Class;
+use xjtlu_timetable::color::ModuleColors;
+use xjtlu_timetable::diff;
+use xjtlu_timetable::ical::{self, ExportOptions, ICalBuilder, ICalElement};
+use xjtlu_timetable::ical_reader::parse_ical;
+use xjtlu_timetable::json;
+use xjtlu_timetable::parser::parse_best_effort;
+use xjtlu_timetable::render;
+use xjtlu_timetable::semester::{current_semester, find_semester};
+use xjtlu_timetable::constants::SEMESTERS;
 use std::rc::Rc;
-use stdweb::web::{INode, IParentNode, IEventTarget, Element};
-use stdweb::web::event::{IEvent, ClickEvent};
-use util::ElementAttribute;
+use web_sys::{Element, Event, File};
+use dialog::{self, Dialog};
+use util;
 
 pub struct App {
     elem_input: Element,
+    elem_file: Element,
+    elem_previous: Element,
+    elem_semester: Element,
+    elem_colors: Element,
+    elem_holidays: Element,
+    elem_milestones: Element,
+    elem_week_numbers: Element,
     elem_submit: Element,
     elem_help: Element,
     dialog_help: StaticDialog,
@@ -16,42 +31,132 @@ pub struct App {
 }
 
 impl App {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
-        elem_input: Element, elem_submit: Element, elem_help: Element,
+        elem_input: Element, elem_file: Element, elem_previous: Element, elem_semester: Element, elem_colors: Element,
+        elem_holidays: Element, elem_milestones: Element, elem_week_numbers: Element, elem_submit: Element, elem_help: Element,
         dialog_help: Element, dialog_download: Element, dialog_info: Element
     ) -> App {
         App {
             elem_input,
+            elem_file,
+            elem_previous,
+            elem_semester,
+            elem_colors,
+            elem_holidays,
+            elem_milestones,
+            elem_week_numbers,
             elem_submit,
             elem_help,
-            dialog_help: StaticDialog(dialog_help),
-            dialog_download: DownloadDialog(dialog_download),
-            dialog_info: InfoDialog(dialog_info)
+            dialog_help: StaticDialog::new(dialog_help),
+            dialog_download: DownloadDialog::new(dialog_download),
+            dialog_info: InfoDialog::new(dialog_info)
         }
     }
 
     pub fn run(self) {
+        // Fill in the semesters we know of, selecting the current one
+        let options: Vec<_> = SEMESTERS.iter().map(|s| (s.id, s.name)).collect();
+        util::fill_select(&self.elem_semester, &options, current_semester().id);
+
         let _self = Rc::new(self);
-        _self.elem_submit.add_event_listener(clone!(_self; |ev: ClickEvent| {
+        util::add_event_listener(&_self.elem_submit, "click", clone!(_self; |ev: Event| {
             _self.on_submit(ev);
         }));
 
-        _self.elem_help.add_event_listener(clone!(_self; |ev: ClickEvent| {
+        util::add_event_listener(&_self.elem_help, "click", clone!(_self; |ev: Event| {
             _self.on_help(ev);
         }));
+
+        // Saved pages can be opened or dropped onto the paste area,
+        // for browsers that cannot paste rich text
+        util::add_event_listener(&_self.elem_file, "change", clone!(_self; |_ev: Event| {
+            if let Some(file) = util::selected_file(&_self.elem_file) {
+                _self.on_file(file);
+                util::clear_selected_file(&_self.elem_file);
+            }
+        }));
+
+        for event in &["dragenter", "dragover"] {
+            util::add_event_listener(&_self.elem_input, event, clone!(_self; |ev: Event| {
+                ev.prevent_default();
+                _self.elem_input.class_list().add_1("drag-over").unwrap();
+            }));
+        }
+
+        util::add_event_listener(&_self.elem_input, "dragleave", clone!(_self; |_ev: Event| {
+            _self.elem_input.class_list().remove_1("drag-over").unwrap();
+        }));
+
+        util::add_event_listener(&_self.elem_input, "drop", clone!(_self; |ev: Event| {
+            _self.elem_input.class_list().remove_1("drag-over").unwrap();
+            if let Some(file) = util::dropped_file(&ev) {
+                ev.prevent_default();
+                _self.on_file(file);
+            }
+        }));
     }
 }
 
 trait AppImpl {
-    fn show_download_dialog(&self, content: String);
+    fn export_options(&self) -> Result<ExportOptions, String>;
+    fn show_download_dialog(&self, classes: &[Class], warnings: &[String], options: &ExportOptions, previous: Option<String>);
     fn show_info_dialog(&self, info: String);
-    fn on_submit(&self, ev: ClickEvent);
-    fn on_help(&self, ev: ClickEvent);
+    fn export(&self, classes: Result<(Vec<Class>, Vec<String>), String>);
+    fn on_submit(&self, ev: Event);
+    fn on_file(&self, file: File);
+    fn on_help(&self, ev: Event);
 }
 
 impl AppImpl for Rc<App> {
-    fn show_download_dialog(&self, content: String) {
-        self.dialog_download.set_download_link(&format!("data:text/calendar;base64,{}", encode(&content)));
+    fn export_options(&self) -> Result<ExportOptions, String> {
+        let semester_id = util::get_input_value(&self.elem_semester);
+        Ok(ExportOptions {
+            semester: find_semester(&semester_id)
+                .ok_or(format!("Unknown semester {}", semester_id))?,
+            colors: ModuleColors::parse(&util::get_input_value(&self.elem_colors))?,
+            holidays: util::is_checked(&self.elem_holidays),
+            milestones: util::is_checked(&self.elem_milestones),
+            week_numbers: util::is_checked(&self.elem_week_numbers)
+        })
+    }
+
+    fn show_download_dialog(&self, classes: &[Class], warnings: &[String], options: &ExportOptions, previous: Option<String>) {
+        self.dialog_download.set_warnings(warnings);
+        let mut events = ical::classes_to_events(classes, options);
+
+        // Compare with the previously exported calendar, if the user selected one
+        match previous.map(|prev| parse_ical(&prev)) {
+            Some(Ok(old)) => {
+                diff::inherit_uids(&old, &mut events);
+                let changes = diff::diff_events(&old, &events);
+                self.dialog_download.set_diff(Some(&diff::format_report(&changes)));
+                if diff::has_unknown_uids(&changes) {
+                    // The report explains why there is nothing to import
+                    self.dialog_download.set_update_links(None);
+                } else {
+                    self.dialog_download.set_update_links(Some((
+                        &calendar_link(&diff::changes_to_request(&changes)),
+                        &calendar_link(&diff::changes_to_cancel(&changes))
+                    )));
+                }
+            },
+            Some(Err(err)) => {
+                self.dialog_download.set_diff(Some(&format!("Failed to read the previous calendar: {}", err)));
+                self.dialog_download.set_update_links(None);
+            },
+            None => {
+                self.dialog_download.set_diff(None);
+                self.dialog_download.set_update_links(None);
+            }
+        }
+
+        self.dialog_download.set_download_link(&calendar_link(&ical::events_to_ical(events, options)));
+        self.dialog_download.set_grid_links(
+            &format!("data:image/svg+xml;base64,{}", encode(&render::classes_to_svg(classes, &options.colors))),
+            &format!("data:application/pdf;base64,{}", encode(&render::classes_to_pdf(classes, &options.colors)))
+        );
+        self.dialog_download.set_json_link(&format!("data:application/json;base64,{}", encode(&json::classes_to_json(classes))));
         self.dialog_download.show();
     }
 
@@ -60,32 +165,62 @@ impl AppImpl for Rc<App> {
         self.dialog_info.show();
     }
 
-    fn on_submit(&self, ev: ClickEvent) {
+    fn on_submit(&self, ev: Event) {
         ev.prevent_default();
-        match parse(&self.elem_input).map(|r| ical::classes_to_ical(&r).serialize()) {
-            Ok(cal) => self.show_download_dialog(cal),
+        // Previously exported JSON timetables can be pasted in place of the E-Bridge page
+        let text = self.elem_input.text_content().unwrap_or("".to_string());
+        let classes = if json::is_json(&text) {
+            json::classes_from_json(&text).map(|classes| (classes, Vec::new()))
+        } else {
+            parse_best_effort(&self.elem_input.inner_html())
+        };
+        self.export(classes);
+    }
+
+    // Read a saved timetable page (or JSON timetable) and export it
+    fn on_file(&self, file: File) {
+        let _self = self.clone();
+        util::read_file(&file, move |content| {
+            let classes = match content {
+                Some(ref content) if json::is_json(content) => json::classes_from_json(content).map(|classes| (classes, Vec::new())),
+                Some(ref content) => parse_best_effort(content),
+                None => Err("Failed to read the file".to_string())
+            };
+            _self.export(classes);
+        });
+    }
+
+    fn export(&self, classes: Result<(Vec<Class>, Vec<String>), String>) {
+        match classes.and_then(|c| self.export_options().map(|o| (c, o))) {
+            Ok(((classes, warnings), options)) => {
+                let _self = self.clone();
+                util::read_selected_file(&self.elem_previous, move |previous| {
+                    _self.show_download_dialog(&classes, &warnings, &options, previous);
+                });
+            },
             Err(err) => self.show_info_dialog(err)
         }
     }
 
-    fn on_help(&self, ev: ClickEvent) {
+    fn on_help(&self, ev: Event) {
         ev.prevent_default();
         self.dialog_help.show();
     }
 }
 
-trait Dialog {
-    fn get_element(&self) -> &Element;
-
-    fn show(&self) {
-        js!(
-            $(@{self.get_element().as_ref()}).modal("show");
-        );
-    }
+fn calendar_link(cal: &ICalBuilder) -> String {
+    format!("data:text/calendar;base64,{}", encode(&cal.serialize()))
 }
 
 struct StaticDialog(Element);
 
+impl StaticDialog {
+    fn new(elem: Element) -> StaticDialog {
+        dialog::init(&elem);
+        StaticDialog(elem)
+    }
+}
+
 impl Dialog for StaticDialog {
     fn get_element(&self) -> &Element {
         &self.0
@@ -95,6 +230,13 @@ impl Dialog for StaticDialog {
 // Dialog to show download link of the ics file
 struct DownloadDialog(Element);
 
+impl DownloadDialog {
+    fn new(elem: Element) -> DownloadDialog {
+        dialog::init(&elem);
+        DownloadDialog(elem)
+    }
+}
+
 impl Dialog for DownloadDialog {
     fn get_element(&self) -> &Element {
         &self.0
@@ -103,13 +245,66 @@ impl Dialog for DownloadDialog {
 
 impl DownloadDialog {
     fn set_download_link(&self, link: &str) {
-        self.get_element().query_selector("#link-download").unwrap().unwrap().set_attribute("href", link.into());
+        self.get_element().query_selector("#link-download").unwrap().unwrap().set_attribute("href", link).unwrap();
+    }
+
+    fn set_json_link(&self, link: &str) {
+        self.get_element().query_selector("#link-download-json").unwrap().unwrap().set_attribute("href", link).unwrap();
+    }
+
+    // Links to the printable weekly grid
+    fn set_grid_links(&self, svg: &str, pdf: &str) {
+        self.get_element().query_selector("#link-download-svg").unwrap().unwrap().set_attribute("href", svg).unwrap();
+        self.get_element().query_selector("#link-download-pdf").unwrap().unwrap().set_attribute("href", pdf).unwrap();
+    }
+
+    // Show what may have been read wrong, or hide the area if there is nothing
+    fn set_warnings(&self, warnings: &[String]) {
+        let area = self.get_element().query_selector("#warnings-area").unwrap().unwrap();
+        if warnings.is_empty() {
+            area.set_attribute("style", "display: none").unwrap();
+        } else {
+            self.get_element().query_selector("#text-warnings").unwrap().unwrap().set_text_content(Some(&warnings.join("\n")));
+            area.set_attribute("style", "").unwrap();
+        }
+    }
+
+    // Show the changes since the previous calendar, or hide the area if there is none
+    fn set_diff(&self, report: Option<&str>) {
+        let area = self.get_element().query_selector("#diff-area").unwrap().unwrap();
+        match report {
+            Some(report) => {
+                self.get_element().query_selector("#text-diff").unwrap().unwrap().set_text_content(Some(report));
+                area.set_attribute("style", "").unwrap();
+            },
+            None => area.set_attribute("style", "display: none").unwrap()
+        }
+    }
+
+    // Links to the calendars updating / cancelling the changed events
+    fn set_update_links(&self, links: Option<(&str, &str)>) {
+        let area = self.get_element().query_selector("#update-area").unwrap().unwrap();
+        match links {
+            Some((request, cancel)) => {
+                self.get_element().query_selector("#link-download-update").unwrap().unwrap().set_attribute("href", request).unwrap();
+                self.get_element().query_selector("#link-download-cancel").unwrap().unwrap().set_attribute("href", cancel).unwrap();
+                area.set_attribute("style", "").unwrap();
+            },
+            None => area.set_attribute("style", "display: none").unwrap()
+        }
     }
 }
 
 // Dialog to show error messages
 struct InfoDialog(Element);
 
+impl InfoDialog {
+    fn new(elem: Element) -> InfoDialog {
+        dialog::init(&elem);
+        InfoDialog(elem)
+    }
+}
+
 impl Dialog for InfoDialog {
     fn get_element(&self) -> &Element {
         &self.0
@@ -118,6 +313,6 @@ impl Dialog for InfoDialog {
 
 impl InfoDialog {
     fn set_information(&self, text: &str) {
-        self.get_element().query_selector("#text-info").unwrap().unwrap().set_text_content(text);
+        self.get_element().query_selector("#text-info").unwrap().unwrap().set_text_content(Some(text));
     }
 }
\ No newline at end of file
@@ -0,0 +1,318 @@
+/*
+ * Command line interface of the timetable exporter.
+ * It works on E-Bridge timetable pages saved as HTML or MHTML,
+ * as well as JSON timetables saved from the web page.
+ */
+extern crate getopts;
+extern crate xjtlu_timetable;
+
+use getopts::{Matches, Options};
+use std::env;
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::path::Path;
+use std::process;
+#[cfg(feature = "caldav")]
+use xjtlu_timetable::caldav::CalDavClient;
+use xjtlu_timetable::class::{Class, ClassTime};
+use xjtlu_timetable::color::ModuleColors;
+use xjtlu_timetable::constants::SEMESTERS;
+use xjtlu_timetable::diff;
+use xjtlu_timetable::free_slots::{self, FreeSlotOptions};
+use xjtlu_timetable::ical::{self, ExportOptions, ICalElement, ICalEvent};
+use xjtlu_timetable::ical_reader::parse_ical;
+use xjtlu_timetable::json;
+use xjtlu_timetable::parser;
+use xjtlu_timetable::query;
+use xjtlu_timetable::render;
+use xjtlu_timetable::semester::{current_semester, find_semester, Semester};
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    let mut opts = Options::new();
+    opts.optopt("o", "output", "write the calendar to FILE instead of stdout", "FILE");
+    opts.optopt("d", "diff", "print the changes since a previously exported calendar", "OLD.ics");
+    opts.optopt("", "update", "with --diff, write the calendar updating moved and added classes to FILE", "FILE");
+    opts.optopt("", "cancel", "with --diff, write the calendar cancelling removed classes to FILE", "FILE");
+    opts.optopt("s", "semester", "the semester of the timetable (default: the current one)", "ID");
+    opts.optflag("", "list-semesters", "list the semesters that can be selected");
+    opts.optflag("", "holidays", "include the holidays of the semester as all-day events");
+    opts.optflag("", "milestones", "include the start and end of the semester and examinations as all-day events");
+    opts.optflag("", "week-numbers", "mark the Monday of each teaching week with an all-day \"Week N\" event");
+    opts.optmulti("c", "color", "use a CSS3 color name for a module, e.g. CPT101=red", "MODULE=COLOR");
+    opts.optopt("", "svg", "draw the timetable as a weekly grid into an SVG FILE", "FILE");
+    opts.optopt("", "pdf", "draw the timetable as a weekly grid into a PDF FILE", "FILE");
+    opts.optopt("", "caldav", "push the classes to a CalDAV calendar, with the password in $CALDAV_PASSWORD", "URL");
+    opts.optopt("", "caldav-user", "the user name for --caldav", "USER");
+    opts.optopt("", "from", "with free, the earliest time of a meeting (default: 9:00)", "HH:MM");
+    opts.optopt("", "to", "with free, the latest time of a meeting (default: 18:00)", "HH:MM");
+    opts.optopt("", "min-length", "with free, the shortest free slot to list (default: 60)", "MINUTES");
+    opts.optflag("", "weekends", "with free, also look at Saturdays and Sundays");
+    opts.optflag("h", "help", "print this help message");
+
+    let matches = match opts.parse(&args[1..]) {
+        Ok(m) => m,
+        Err(err) => fail(&err.to_string())
+    };
+
+    if matches.opt_present("list-semesters") {
+        for semester in SEMESTERS {
+            println!("{}\t{}", semester.id, semester.name);
+        }
+        return;
+    }
+
+    if matches.opt_present("h") || matches.free.is_empty() {
+        print_usage(&args[0], &opts);
+        return;
+    }
+
+    let semester = match matches.opt_str("s") {
+        Some(id) => find_semester(&id)
+            .unwrap_or_else(|| fail(&format!("Unknown semester {}, see --list-semesters", id))),
+        None => current_semester()
+    };
+
+    if matches.free[0] == "free" {
+        let timetables: Vec<Vec<Class>> = matches.free[1..].iter()
+            .map(|path| load_timetable(path).unwrap_or_else(|err| fail(&err)))
+            .collect();
+        run_free_slots(&timetables, semester, &matches);
+        return;
+    }
+
+    let (classes, command) = if matches.free[0] == "merge" {
+        (merge_timetables(&matches.free[1..]).unwrap_or_else(|err| fail(&err)), &[][..])
+    } else {
+        // The timetable always comes last, after the command if any
+        let (timetable, command) = matches.free.split_last().unwrap();
+        (load_timetable(timetable).unwrap_or_else(|err| fail(&err)), command)
+    };
+    let options = ExportOptions {
+        semester,
+        colors: ModuleColors::parse(&matches.opt_strs("c").join(","))
+            .unwrap_or_else(|err| fail(&err)),
+        holidays: matches.opt_present("holidays"),
+        milestones: matches.opt_present("milestones"),
+        week_numbers: matches.opt_present("week-numbers")
+    };
+    if !command.is_empty() {
+        run_query(command, &classes, semester);
+        return;
+    }
+
+    if let Some(path) = matches.opt_str("svg") {
+        write_output(Some(path), render::classes_to_svg(&classes, &options.colors).as_bytes())
+            .unwrap_or_else(|err| fail(&err));
+    }
+
+    if let Some(path) = matches.opt_str("pdf") {
+        write_output(Some(path), &render::classes_to_pdf(&classes, &options.colors))
+            .unwrap_or_else(|err| fail(&err));
+    }
+
+    let mut events = ical::classes_to_events(&classes, &options);
+    if let Some(old_path) = matches.opt_str("d") {
+        let old = read_file(&old_path)
+            .and_then(|content| parse_ical(&content))
+            .unwrap_or_else(|err| fail(&err));
+        diff::inherit_uids(&old, &mut events);
+        let changes = diff::diff_events(&old, &events);
+        print!("{}", diff::format_report(&changes));
+        if diff::has_unknown_uids(&changes) && (matches.opt_present("update") || matches.opt_present("cancel")) {
+            fail("--update and --cancel cannot be used with a calendar exported without event UIDs");
+        }
+
+        if let Some(path) = matches.opt_str("update") {
+            write_output(Some(path), diff::changes_to_request(&changes).serialize().as_bytes())
+                .unwrap_or_else(|err| fail(&err));
+        }
+
+        if let Some(path) = matches.opt_str("cancel") {
+            write_output(Some(path), diff::changes_to_cancel(&changes).serialize().as_bytes())
+                .unwrap_or_else(|err| fail(&err));
+        }
+
+    } else if matches.opt_present("update") || matches.opt_present("cancel") {
+        fail("--update and --cancel require --diff");
+    }
+
+    if let Some(url) = matches.opt_str("caldav") {
+        push_caldav(&url, matches.opt_str("caldav-user"), events.clone())
+            .unwrap_or_else(|err| fail(&err));
+    }
+
+    // If anything else was asked for, only write the new calendar
+    // when explicitly asked to, so that stdout is not mixed up with it
+    let other_output = ["d", "svg", "pdf", "caldav"].iter().any(|o| matches.opt_present(o));
+    if other_output && !matches.opt_present("o") {
+        return;
+    }
+
+    let cal = ical::events_to_ical(events, &options).serialize();
+    write_output(matches.opt_str("o"), cal.as_bytes()).unwrap_or_else(|err| fail(&err));
+}
+
+fn print_usage(program: &str, opts: &Options) {
+    let brief = format!(
+        "Usage: {0} [options] TIMETABLE\n       {0} [options] COMMAND TIMETABLE\n       \
+        {0} [options] free TIMETABLE...\n       \
+        {0} [options] merge [NAME=]TIMETABLE...\n\n\
+        TIMETABLE is an E-Bridge timetable page saved as HTML or MHTML, or a JSON timetable.\n\n\
+        Commands:\n    \
+        today          list the classes of today\n    \
+        next           show the class going on now or the next one\n    \
+        week N [DAY]   list the classes of teaching week N, or only those of DAY (e.g. tue)\n    \
+        free           list the times at which all of the timetables are free\n    \
+        merge          export the timetables of several people as one calendar,\n                   \
+        naming the owners (by default the file names) of each class",
+        program
+    );
+    print!("{}", opts.usage(&brief));
+}
+
+/*
+ * Answer a question about the timetable instead of exporting it
+ */
+fn run_query(command: &[String], classes: &[Class], semester: &Semester) {
+    let sessions = match (command[0].as_str(), &command[1..]) {
+        ("today", &[]) => query::sessions_on(classes, semester, &query::now().date()),
+        ("next", &[]) => query::next_session(classes, semester, &query::now()).into_iter().collect(),
+        ("week", args) if args.len() == 1 || args.len() == 2 => {
+            let week = args[0].parse::<usize>().ok()
+                .filter(|w| *w >= 1 && *w <= semester.teaching_weeks().len())
+                .unwrap_or_else(|| fail(&format!(
+                    "{} has teaching weeks 1 to {}", semester.name, semester.teaching_weeks().len()
+                )));
+            let weekday = args.get(1).map(|day| {
+                query::parse_weekday(day).unwrap_or_else(|| fail(&format!("Unknown weekday {}", day)))
+            });
+            query::sessions_in_week(classes, semester, week, weekday)
+        },
+        _ => fail(&format!("Unknown command {}, see --help", command.join(" ")))
+    };
+    print!("{}", query::format_sessions(&sessions));
+}
+
+fn fail(msg: &str) -> ! {
+    eprintln!("{}", msg);
+    process::exit(1);
+}
+
+/*
+ * Find the common free time of several timetables
+ * The table goes to stdout, and the calendar of the slots to `--output` if given
+ */
+fn run_free_slots(timetables: &[Vec<Class>], semester: &Semester, matches: &Matches) {
+    if timetables.is_empty() {
+        fail("free requires at least one timetable");
+    }
+
+    let mut options = FreeSlotOptions::default();
+    if let Some(time) = matches.opt_str("from") {
+        options.day_start = ClassTime::parse(&time)
+            .unwrap_or_else(|| fail(&format!("Invalid time {}, expected e.g. 9:00 or 13:30", time)));
+    }
+    if let Some(time) = matches.opt_str("to") {
+        options.day_end = ClassTime::parse(&time)
+            .unwrap_or_else(|| fail(&format!("Invalid time {}, expected e.g. 18:00 or 17:30", time)));
+    }
+    if let Some(minutes) = matches.opt_str("min-length") {
+        let minutes: usize = minutes.parse()
+            .unwrap_or_else(|_| fail(&format!("Invalid length {}", minutes)));
+        // Round up to whole half-hours
+        options.min_len = minutes.div_ceil(30);
+    }
+    options.weekends = matches.opt_present("weekends");
+
+    let slots = free_slots::find_free_slots(timetables, semester, &options);
+    print!("{}", free_slots::format_free_slots(&slots));
+    if let Some(path) = matches.opt_str("o") {
+        let cal = free_slots::free_slots_to_ical(&slots, timetables.len()).serialize();
+        write_output(Some(path), cal.as_bytes()).unwrap_or_else(|err| fail(&err));
+    }
+}
+
+/*
+ * Bring a CalDAV calendar in line with the events of the timetable
+ * The password is not taken from the command line, where others could see it.
+ */
+#[cfg(feature = "caldav")]
+fn push_caldav(url: &str, user: Option<String>, events: Vec<ICalEvent>) -> Result<(), String> {
+    let password = env::var("CALDAV_PASSWORD").ok();
+    let client = CalDavClient::new(url, user.as_deref(), password.as_deref());
+    print!("{}", client.push(events)?);
+    Ok(())
+}
+
+#[cfg(not(feature = "caldav"))]
+fn push_caldav(_url: &str, _user: Option<String>, _events: Vec<ICalEvent>) -> Result<(), String> {
+    Err("--caldav requires building with --features caldav".to_string())
+}
+
+/*
+ * Read the whole content of a file
+ * `-` stands for stdin
+ */
+fn read_file(path: &str) -> Result<String, String> {
+    let mut content = String::new();
+    if path == "-" {
+        io::stdin().read_to_string(&mut content)
+    } else {
+        File::open(path).and_then(|mut f| f.read_to_string(&mut content))
+    }.map_err(|e| format!("Failed to read {}: {}", path, e))?;
+    Ok(content)
+}
+
+/*
+ * Load a timetable from a saved E-Bridge page or JSON file
+ */
+fn load_timetable(path: &str) -> Result<Vec<Class>, String> {
+    let content = read_file(path)?;
+    if json::is_json(&content) {
+        return json::classes_from_json(&content).map_err(|err| format!("{}: {}", path, err));
+    }
+
+    let (classes, warnings) = parser::parse_best_effort(&content)
+        .map_err(|err| format!("{}: {}", path, err))?;
+    for warning in warnings {
+        eprintln!("{}: warning: {}", path, warning);
+    }
+    Ok(classes)
+}
+
+/*
+ * Load the timetables of several people into one list of classes
+ * Each argument is `NAME=FILE`, or just `FILE` to name the owner after the file.
+ */
+fn merge_timetables(args: &[String]) -> Result<Vec<Class>, String> {
+    if args.is_empty() {
+        return Err("merge requires at least one timetable".to_string());
+    }
+
+    let mut ret = Vec::new();
+    for arg in args {
+        let (owner, path) = match arg.find('=') {
+            Some(i) => (arg[..i].to_string(), &arg[(i + 1)..]),
+            None => (
+                Path::new(arg).file_stem().map_or(arg.to_string(), |s| s.to_string_lossy().into_owned()),
+                arg.as_str()
+            )
+        };
+        for mut class in load_timetable(path)? {
+            class.owner = Some(owner.clone());
+            ret.push(class);
+        }
+    }
+    Ok(ret)
+}
+
+fn write_output(path: Option<String>, content: &[u8]) -> Result<(), String> {
+    match path {
+        Some(path) => File::create(&path)
+            .and_then(|mut f| f.write_all(content))
+            .map_err(|e| format!("Failed to write {}: {}", path, e)),
+        None => io::stdout().write_all(content)
+            .map_err(|e| format!("Failed to write output: {}", e))
+    }
+}
@@ -0,0 +1,323 @@
+/*
+ * A tiny HTTP server keeping timetables and serving them as
+ * calendar feeds, so that calendar apps subscribing to them
+ * pick up corrections of the timetable automatically.
+ *
+ *   POST   /timetables            store a timetable (E-Bridge HTML or JSON),
+ *                                 answers with the URL of its feed and its edit token
+ *   PUT    /timetables/ID         replace a stored timetable, keeping its URL
+ *   GET    /timetables/ID.ics     the calendar, generated on each request
+ *   GET    /timetables/ID.json    the stored timetable
+ *   DELETE /timetables/ID         forget a timetable
+ *
+ * The feed URL is handed out to calendar apps, so it only allows reading.
+ * Changing a timetable takes the edit token given when it was stored,
+ * as `Authorization: Bearer TOKEN`.
+ *
+ * The export options are taken from the query string of the feed, e.g.
+ * `/timetables/ID.ics?semester=2025-26-s1&holidays&week-numbers&colors=CPT101%3Dred`
+ * Options given when storing a timetable go into the URL of its feed,
+ * which always names the semester (the current one if not given),
+ * so that the dates of a feed do not move to the next semester.
+ *
+ * Only built with `--features server`.
+ */
+extern crate getopts;
+extern crate tiny_http;
+extern crate xjtlu_timetable;
+
+use getopts::Options;
+use std::env;
+use std::fs::{self, File};
+use std::io::{self, Cursor, Read, Write};
+use std::panic::{self, AssertUnwindSafe};
+use std::path::PathBuf;
+use std::process;
+use tiny_http::{Header, Method, Request, Response, Server};
+use xjtlu_timetable::class::Class;
+use xjtlu_timetable::color::ModuleColors;
+use xjtlu_timetable::ical::{self, ExportOptions, ICalElement};
+use xjtlu_timetable::json;
+use xjtlu_timetable::parser;
+use xjtlu_timetable::semester::find_semester;
+
+const MAX_UPLOAD_SIZE: u64 = 4 * 1024 * 1024;
+
+struct Store {
+    dir: PathBuf
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    let mut opts = Options::new();
+    opts.optopt("l", "listen", "the address to listen on (default: 127.0.0.1:8080)", "ADDR");
+    opts.optopt("d", "data", "the directory to keep the timetables in (default: ./timetables)", "DIR");
+    opts.optflag("h", "help", "print this help message");
+
+    let matches = match opts.parse(&args[1..]) {
+        Ok(m) => m,
+        Err(err) => fail(&err.to_string())
+    };
+
+    if matches.opt_present("h") || !matches.free.is_empty() {
+        print!("{}", opts.usage(&format!("Usage: {} [options]", args[0])));
+        return;
+    }
+
+    let store = Store {
+        dir: PathBuf::from(matches.opt_str("d").unwrap_or("timetables".to_string()))
+    };
+    fs::create_dir_all(&store.dir)
+        .unwrap_or_else(|e| fail(&format!("Failed to create {}: {}", store.dir.display(), e)));
+
+    let addr = matches.opt_str("l").unwrap_or("127.0.0.1:8080".to_string());
+    let server = Server::http(&addr)
+        .unwrap_or_else(|e| fail(&format!("Failed to listen on {}: {}", addr, e)));
+    println!("Listening on http://{}/", addr);
+
+    for mut request in server.incoming_requests() {
+        let url = request.url().to_string();
+        // A request hitting a bug must not take the whole server down with it
+        let response = panic::catch_unwind(AssertUnwindSafe(|| handle(&store, &mut request)))
+            .unwrap_or_else(|_| Response::from_string("Internal server error\n").with_status_code(500));
+        if let Err(e) = request.respond(response) {
+            eprintln!("Failed to respond to {}: {}", url, e);
+        }
+    }
+}
+
+fn fail(msg: &str) -> ! {
+    eprintln!("{}", msg);
+    process::exit(1);
+}
+
+fn handle(store: &Store, request: &mut Request) -> Response<Cursor<Vec<u8>>> {
+    let url = request.url().to_string();
+    let (path, query) = match url.find('?') {
+        Some(i) => (&url[..i], &url[(i + 1)..]),
+        None => (url.as_str(), "")
+    };
+    let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+
+    let result = match (request.method().clone(), segments.as_slice()) {
+        (Method::Post, &["timetables"]) => export_options(query)
+            .and_then(|options| read_timetable(request).map(|classes| (classes, options)))
+            .and_then(|(classes, options)| store.create(&classes).map(|created| (created, options)))
+            .map(|((id, token), options)| {
+                let location = format!("/timetables/{}.ics?{}", id, feed_query(query, &options));
+                let feed = format!("webcal://{}{}", host(request), location);
+                Response::from_string(format!("{}\nEdit token: {}\n", feed, token))
+                    .with_status_code(201)
+                    .with_header(header("Location", &location))
+            }),
+        (Method::Put, &["timetables", id]) => store.authorize(id, request)
+            .and_then(|_| read_timetable(request))
+            .and_then(|classes| store.replace(id, &classes))
+            .map(|_| Response::from_string("Updated\n")),
+        (Method::Delete, &["timetables", id]) => store.authorize(id, request)
+            .and_then(|_| store.remove(id))
+            .map(|_| Response::from_string("Deleted\n")),
+        (Method::Get, &["timetables", file]) if file.ends_with(".ics") => store.load(&file[..(file.len() - 4)])
+            .and_then(|classes| {
+                let options = export_options(query)?;
+                let cal = ical::classes_to_ical(&classes, &options).serialize();
+                Ok(Response::from_string(cal)
+                    .with_header(header("Content-Type", "text/calendar; charset=utf-8")))
+            }),
+        (Method::Get, &["timetables", file]) if file.ends_with(".json") => store.load(&file[..(file.len() - 5)])
+            .map(|classes| {
+                Response::from_string(json::classes_to_json(&classes))
+                    .with_header(header("Content-Type", "application/json; charset=utf-8"))
+            }),
+        _ => Err((404, "Not found".to_string()))
+    };
+
+    result.unwrap_or_else(|(status, msg)| {
+        Response::from_string(format!("{}\n", msg)).with_status_code(status)
+    })
+}
+
+/*
+ * Parse the body of a request as a timetable
+ */
+fn read_timetable(request: &mut Request) -> Result<Vec<Class>, (u16, String)> {
+    let mut body = String::new();
+    request.as_reader().take(MAX_UPLOAD_SIZE + 1).read_to_string(&mut body)
+        .map_err(|e| (400, format!("Failed to read the timetable: {}", e)))?;
+    if body.len() as u64 > MAX_UPLOAD_SIZE {
+        return Err((413, "The timetable is too large".to_string()));
+    }
+
+    if json::is_json(&body) {
+        json::classes_from_json(&body)
+    } else {
+        parser::parse_saved_page(&body)
+    }.map_err(|err| (400, err))
+}
+
+/*
+ * The export options given in the query string of a feed
+ */
+fn export_options(query: &str) -> Result<ExportOptions, (u16, String)> {
+    let mut options = ExportOptions::default();
+    for pair in query.split('&').filter(|p| !p.is_empty()) {
+        let (key, value) = match pair.find('=') {
+            Some(i) => (&pair[..i], percent_decode(&pair[(i + 1)..])),
+            None => (pair, "".to_string())
+        };
+        // Flags are set unless explicitly turned off
+        let flag = value != "0" && value != "false";
+        match key {
+            "semester" => options.semester = find_semester(&value)
+                .ok_or((400, format!("Unknown semester {}", value)))?,
+            "holidays" => options.holidays = flag,
+            "milestones" => options.milestones = flag,
+            "week-numbers" => options.week_numbers = flag,
+            "colors" => options.colors = ModuleColors::parse(&value).map_err(|err| (400, err))?,
+            _ => return Err((400, format!("Unknown option {}", key)))
+        }
+    }
+    Ok(options)
+}
+
+// The query string of a new feed, naming its semester
+fn feed_query(query: &str, options: &ExportOptions) -> String {
+    let named = query.split('&').any(|p| p == "semester" || p.starts_with("semester="));
+    match (named, query.is_empty()) {
+        (true, _) => query.to_string(),
+        (false, true) => format!("semester={}", options.semester.id),
+        (false, false) => format!("semester={}&{}", options.semester.id, query)
+    }
+}
+
+fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let hex = |i: usize| bytes.get(i).and_then(|b| (*b as char).to_digit(16));
+    let mut ret = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        match (bytes[i], hex(i + 1), hex(i + 2)) {
+            (b'+', _, _) => ret.push(b' '),
+            (b'%', Some(high), Some(low)) => {
+                ret.push((high * 16 + low) as u8);
+                i += 2;
+            },
+            (b, _, _) => ret.push(b)
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&ret).into_owned()
+}
+
+fn host(request: &Request) -> String {
+    request.headers().iter()
+        .find(|h| h.field.equiv("Host"))
+        .map(|h| h.value.as_str().to_string())
+        .unwrap_or("localhost".to_string())
+}
+
+// The token of an `Authorization: Bearer TOKEN` header
+fn bearer_token(request: &Request) -> Option<&str> {
+    request.headers().iter()
+        .find(|h| h.field.equiv("Authorization"))
+        .and_then(|h| {
+            let value = h.value.as_str().trim();
+            let (scheme, token) = value.split_at(value.find(' ')?);
+            if scheme.eq_ignore_ascii_case("Bearer") { Some(token.trim()) } else { None }
+        })
+}
+
+fn header(name: &str, value: &str) -> Header {
+    Header::from_bytes(name.as_bytes(), value.as_bytes()).unwrap()
+}
+
+impl Store {
+    /*
+     * Store a new timetable
+     * Returns its id and its edit token.
+     */
+    fn create(&self, classes: &[Class]) -> Result<(String, String), (u16, String)> {
+        // The URL of the feed is all it takes to read a timetable,
+        // and the token to change it, so neither must be guessable
+        let id = random_hex()?;
+        let token = random_hex()?;
+        File::create(self.token_path(&id)?)
+            .and_then(|mut f| f.write_all(token.as_bytes()))
+            .map_err(|e| (500, format!("Failed to store the timetable: {}", e)))?;
+        self.write(&id, classes)?;
+        Ok((id, token))
+    }
+
+    // Check the edit token given with a request changing a timetable
+    fn authorize(&self, id: &str, request: &Request) -> Result<(), (u16, String)> {
+        let given = bearer_token(request)
+            .ok_or((401, "The edit token is required as `Authorization: Bearer TOKEN`".to_string()))?;
+        let mut token = String::new();
+        File::open(self.token_path(id)?)
+            .and_then(|mut f| f.read_to_string(&mut token))
+            .map_err(not_found)?;
+        if !constant_time_eq(given.as_bytes(), token.trim().as_bytes()) {
+            return Err((403, "Wrong edit token".to_string()));
+        }
+        Ok(())
+    }
+
+    fn replace(&self, id: &str, classes: &[Class]) -> Result<(), (u16, String)> {
+        self.path(id)?;
+        self.load(id)?;
+        self.write(id, classes)
+    }
+
+    fn remove(&self, id: &str) -> Result<(), (u16, String)> {
+        fs::remove_file(self.path(id)?).map_err(not_found)?;
+        fs::remove_file(self.token_path(id)?).map_err(not_found)
+    }
+
+    fn load(&self, id: &str) -> Result<Vec<Class>, (u16, String)> {
+        let mut content = String::new();
+        File::open(self.path(id)?)
+            .and_then(|mut f| f.read_to_string(&mut content))
+            .map_err(not_found)?;
+        json::classes_from_json(&content).map_err(|err| (500, err))
+    }
+
+    fn write(&self, id: &str, classes: &[Class]) -> Result<(), (u16, String)> {
+        File::create(self.path(id)?)
+            .and_then(|mut f| f.write_all(json::classes_to_json(classes).as_bytes()))
+            .map_err(|e| (500, format!("Failed to store the timetable: {}", e)))
+    }
+
+    // Only accept ids we could have generated, so that no other file can be reached
+    fn path(&self, id: &str) -> Result<PathBuf, (u16, String)> {
+        if id.is_empty() || !id.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err((404, "Not found".to_string()));
+        }
+        Ok(self.dir.join(format!("{}.json", id)))
+    }
+
+    fn token_path(&self, id: &str) -> Result<PathBuf, (u16, String)> {
+        self.path(id).map(|p| p.with_extension("token"))
+    }
+}
+
+fn not_found(e: io::Error) -> (u16, String) {
+    if e.kind() == io::ErrorKind::NotFound {
+        (404, "Not found".to_string())
+    } else {
+        (500, e.to_string())
+    }
+}
+
+// 128 random bits from the operating system, in hex
+fn random_hex() -> Result<String, (u16, String)> {
+    let mut bytes = [0u8; 16];
+    File::open("/dev/urandom")
+        .and_then(|mut f| f.read_exact(&mut bytes))
+        .map_err(|e| (500, format!("Failed to generate a random id: {}", e)))?;
+    Ok(bytes.iter().map(|b| format!("{:02x}", b)).collect())
+}
+
+// Compare secrets without telling how much of them matched by the time it takes
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
@@ -0,0 +1,250 @@
+/*
+ * Push the events of a timetable to a CalDAV calendar (RFC 4791),
+ * e.g. on Nextcloud or Radicale.
+ * Each event is stored as its own resource named after its UID.
+ * Events already on the server are only rewritten when they changed,
+ * and events of classes that are gone are deleted. Events not exported
+ * by this tool are never touched, nor are ours sharing a resource with them.
+ * A resource holding several of our events (e.g. imported by other means)
+ * is rewritten or deleted as a whole.
+ *
+ * Only built with `--features caldav`.
+ */
+use base64;
+use diff;
+use html::{parse_html, Element};
+use ical::{ICalBuilder, ICalElement, ICalEvent};
+use ical_reader::parse_ical;
+use std::fmt;
+use std::slice;
+use ureq;
+
+const UID_SUFFIX: &str = "@xjtlu-timetable";
+
+const CALENDAR_QUERY: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<c:calendar-query xmlns:d="DAV:" xmlns:c="urn:ietf:params:xml:ns:caldav">
+  <d:prop><d:getetag/><c:calendar-data/></d:prop>
+  <c:filter><c:comp-filter name="VCALENDAR"><c:comp-filter name="VEVENT"/></c:comp-filter></c:filter>
+</c:calendar-query>"#;
+
+pub struct CalDavClient {
+    agent: ureq::Agent,
+    collection: String, // URL of the calendar, ending with `/`
+    authorization: Option<String>
+}
+
+/*
+ * A calendar object stored on the server, with the events we exported in it
+ */
+struct Resource {
+    href: String,
+    etag: Option<String>,
+    events: Vec<ICalEvent>,
+    foreign: bool // Also holds events not exported by this tool
+}
+
+#[derive(Default)]
+pub struct PushReport {
+    pub created: usize,
+    pub updated: usize,
+    pub deleted: usize,
+    pub unchanged: usize,
+    pub skipped: usize // Sharing a resource with events not exported by this tool
+}
+
+impl fmt::Display for PushReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} created, {} updated, {} deleted, {} unchanged",
+            self.created, self.updated, self.deleted, self.unchanged)?;
+        if self.skipped > 0 {
+            write!(f, ", {} skipped as they share a resource with other events", self.skipped)?;
+        }
+        writeln!(f)
+    }
+}
+
+impl CalDavClient {
+    pub fn new(collection: &str, user: Option<&str>, password: Option<&str>) -> CalDavClient {
+        let mut collection = collection.to_string();
+        if !collection.ends_with('/') {
+            collection.push('/');
+        }
+        CalDavClient {
+            agent: ureq::AgentBuilder::new().build(),
+            collection,
+            authorization: user.map(|user| format!(
+                "Basic {}", base64::encode(&format!("{}:{}", user, password.unwrap_or("")))
+            ))
+        }
+    }
+
+    fn request(&self, method: &str, url: &str) -> ureq::Request {
+        let request = self.agent.request(method, url);
+        match self.authorization {
+            Some(ref auth) => request.set("Authorization", auth),
+            None => request
+        }
+    }
+
+    /*
+     * Bring the calendar in line with `events`
+     * Classes that moved keep the UIDs of their events on the server
+     * (see `diff::inherit_uids`), so they are updated in place.
+     */
+    pub fn push(&self, mut events: Vec<ICalEvent>) -> Result<PushReport, String> {
+        let remote = self.fetch()?;
+        let old: Vec<ICalEvent> = remote.iter().flat_map(|r| r.events.iter().cloned()).collect();
+        diff::inherit_uids(&old, &mut events);
+
+        let mut report = PushReport::default();
+        // Each resource is written at most once, as its ETag changes on each write
+        for r in &remote {
+            // The new revisions of the events of the resource, leaving out the removed ones
+            let mut kept = Vec::new();
+            let mut updated = 0;
+            for old in &r.events {
+                if let Some(event) = events.iter_mut().find(|e| e.uid == old.uid) {
+                    if !same_event(old, event) {
+                        // Let calendar apps know this is a newer revision
+                        event.sequence = old.sequence + 1;
+                        updated += 1;
+                    }
+                    kept.push(event.clone());
+                }
+            }
+            let deleted = r.events.len() - kept.len();
+
+            if updated == 0 && deleted == 0 {
+                report.unchanged += kept.len();
+                continue;
+            }
+            if r.foreign {
+                report.skipped += r.events.len();
+                continue;
+            }
+
+            let request = self.request(if kept.is_empty() { "DELETE" } else { "PUT" }, &self.resolve(&r.href));
+            let request = match r.etag {
+                Some(ref etag) => request.set("If-Match", etag),
+                None => request
+            };
+            if kept.is_empty() {
+                send(request, None)?;
+            } else {
+                send(request, Some(&resource_calendar(&kept)))?;
+            }
+            report.updated += updated;
+            report.deleted += deleted;
+            report.unchanged += kept.len() - updated;
+        }
+
+        for event in events.iter().filter(|e| !old.iter().any(|o| o.uid == e.uid)) {
+            let url = format!("{}{}.ics", self.collection, encode_path_segment(&event.uid));
+            let request = self.request("PUT", &url).set("If-None-Match", "*");
+            send(request, Some(&resource_calendar(slice::from_ref(event))))?;
+            report.created += 1;
+        }
+        Ok(report)
+    }
+
+    /*
+     * The events on the server exported by this tool
+     */
+    fn fetch(&self) -> Result<Vec<Resource>, String> {
+        let request = self.request("REPORT", &self.collection)
+            .set("Depth", "1")
+            .set("Content-Type", "application/xml; charset=utf-8");
+        let body = send(request, Some(CALENDAR_QUERY))?;
+
+        let mut ret = Vec::new();
+        let document = parse_html(&body);
+        for response in find_all(&document, "response") {
+            let href = match find_all(response, "href").first() {
+                Some(href) => href.text_content().trim().to_string(),
+                None => continue
+            };
+            let etag = find_all(response, "getetag").first()
+                .map(|e| e.text_content().trim().to_string())
+                .filter(|e| !e.is_empty());
+            let data = match find_all(response, "calendar-data").first() {
+                Some(data) => data.text_content(),
+                None => continue
+            };
+
+            // Skip anything that cannot be read, which we did not write anyway
+            let (ours, others): (Vec<ICalEvent>, Vec<ICalEvent>) = parse_ical(&data).unwrap_or_default()
+                .into_iter()
+                .partition(|e| e.uid.ends_with(UID_SUFFIX));
+            if !ours.is_empty() {
+                ret.push(Resource { href, etag, events: ours, foreign: !others.is_empty() });
+            }
+        }
+        Ok(ret)
+    }
+
+    // The URL of a resource, given as an absolute path by the server
+    fn resolve(&self, href: &str) -> String {
+        if href.starts_with("http://") || href.starts_with("https://") {
+            return href.to_string();
+        }
+        // Keep the scheme and host of the collection
+        let origin_end = self.collection.find("://")
+            .and_then(|i| self.collection[(i + 3)..].find('/').map(|j| i + 3 + j))
+            .unwrap_or(self.collection.len());
+        format!("{}{}", &self.collection[..origin_end], href)
+    }
+}
+
+fn send(request: ureq::Request, body: Option<&str>) -> Result<String, String> {
+    let method = request.method().to_string();
+    let url = request.url().to_string();
+    let result = match body {
+        Some(body) if method == "PUT" => request
+            .set("Content-Type", "text/calendar; charset=utf-8")
+            .send_string(body),
+        Some(body) => request.send_string(body),
+        None => request.call()
+    };
+
+    match result {
+        Ok(response) => response.into_string()
+            .map_err(|e| format!("Failed to read the response of {} {}: {}", method, url, e)),
+        Err(ureq::Error::Status(412, _)) => Err(format!(
+            "{} was changed on the server meanwhile, please try again", url
+        )),
+        Err(ureq::Error::Status(code, response)) => Err(format!(
+            "{} {} failed: {} {}", method, url, code, response.status_text()
+        )),
+        Err(e) => Err(format!("{} {} failed: {}", method, url, e))
+    }
+}
+
+// Elements by their name without the namespace prefix, e.g. `d:href`
+fn find_all<'a>(elem: &'a Element, name: &str) -> Vec<&'a Element> {
+    elem.descendants().into_iter()
+        .filter(|e| e.name.rsplit(':').next() == Some(name))
+        .collect()
+}
+
+fn same_event(a: &ICalEvent, b: &ICalEvent) -> bool {
+    a.same_content(b) && a.color == b.color && a.categories == b.categories
+}
+
+// A calendar containing nothing but the events, as stored on the server
+fn resource_calendar(events: &[ICalEvent]) -> String {
+    let mut builder = ICalBuilder::new();
+    for event in events {
+        builder.add(event.clone());
+    }
+    // CalDAV servers expect the line breaks of RFC 5545
+    builder.serialize().replace("\n", "\r\n")
+}
+
+fn encode_path_segment(s: &str) -> String {
+    s.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'@' => (b as char).to_string(),
+            _ => format!("%{:02X}", b)
+        })
+        .collect()
+}
@@ -0,0 +1,106 @@
+/*
+ * Data structures describing XJTLU classes.
+ * These are shared by the web frontend and the command line tool,
+ * so nothing in here may depend on the browser.
+ */
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ClassTime {
+    pub hour: usize,
+    pub half: bool
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Class {
+    pub title: String,
+    pub lecturer: String,
+    pub location: String,
+    pub day: usize, // Day in a week
+    pub weeks: [bool; 14], // Specify whether a class is available on week x
+    pub start: ClassTime, 
+    pub len: usize, // length in half-hours
+    // Whose class it is, when several timetables are merged into one calendar
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub owner: Option<String>
+}
+
+impl Class {
+    /*
+     * The module code (e.g. `CPT101`) at the beginning of the title
+     * Falls back to the whole title if there is none.
+     */
+    pub fn module_code(&self) -> &str {
+        let title = self.title.trim();
+        let end = title.find(|c: char| !c.is_ascii_alphanumeric())
+            .unwrap_or(title.len());
+        if end == 0 {
+            title
+        } else {
+            &title[..end]
+        }
+    }
+
+    /*
+     * Format the weeks of the class the way E-Bridge does
+     * e.g. `1-5, 7, 9-13`
+     */
+    pub fn week_ranges(&self) -> String {
+        let mut ranges: Vec<String> = Vec::new();
+        let mut start: Option<usize> = None;
+        // One extra iteration to close the last range
+        for i in 0..(self.weeks.len() + 1) {
+            let available = i < self.weeks.len() && self.weeks[i];
+            match (start, available) {
+                (None, true) => start = Some(i + 1),
+                (Some(s), false) => {
+                    if s == i {
+                        ranges.push(format!("{}", s));
+                    } else {
+                        ranges.push(format!("{}-{}", s, i));
+                    }
+                    start = None;
+                },
+                _ => ()
+            }
+        }
+        ranges.join(", ")
+    }
+
+    // Whether the class takes place on week x (starting from 1)
+    pub fn has_week(&self, week: usize) -> bool {
+        week > 0 && week <= self.weeks.len() && self.weeks[week - 1]
+    }
+
+    // End time of the class in half-hours since midnight
+    pub fn end_half_hours(&self) -> usize {
+        self.start.half_hours() + self.len
+    }
+}
+
+impl ClassTime {
+    // Half-hours since midnight
+    pub fn half_hours(&self) -> usize {
+        self.hour * 2 + if self.half { 1 } else { 0 }
+    }
+
+    pub fn from_half_hours(half_hours: usize) -> ClassTime {
+        ClassTime { hour: half_hours / 2, half: half_hours % 2 == 1 }
+    }
+
+    /*
+     * Parse a time on the hour or half past, e.g. `9`, `9:00` or `14:30`
+     */
+    pub fn parse(s: &str) -> Option<ClassTime> {
+        let mut parts = s.trim().splitn(2, ':');
+        let hour: usize = parts.next()?.trim().parse().ok()?;
+        let half = match parts.next().map(|m| m.trim()) {
+            None | Some("00") | Some("0") => false,
+            Some("30") => true,
+            _ => return None
+        };
+        if hour > 24 || (hour == 24 && half) {
+            return None;
+        }
+        Some(ClassTime { hour, half })
+    }
+}
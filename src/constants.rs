@@ -1,26 +1,69 @@
 /*
  * Values in this file correspond to the school calendar.
- * This file should be updated once a new school calendar is released
- * i.e. each semester.
+ * A new entry should be added here once a new school calendar
+ * is released, i.e. each semester. Old entries are kept so that
+ * timetables of past semesters can still be exported.
  */
+use semester::{MakeupDay, Period, Semester};
 
-/*
- * Duration of the current semester.
- */
-pub const SEMESTER_START: (u32, u32) = (2, 17); // The day before the semester starts
-pub const SEMESTER_END: (u32, u32) = (5, 25); // The day the semester ends
-
-/*
- * Days on which we will not have any classes
- */
-pub const VACATION_DAYS: &'static [(u32, u32)] = &[
-    (4, 5),
-    (5, 1)
-];
-
-/*
- * Weeks that do not count into weeks in a semester
- */
-pub const VACATION_WEEKS: &'static [(u32, u32)] = &[
-    // Nothing here yet.
+pub const SEMESTERS: &[Semester] = &[
+    Semester {
+        id: "2017-18-s2",
+        name: "2017-18 Semester 2",
+        start: (2018, 2, 17),
+        end: (2018, 5, 25),
+        holidays: &[
+            Period { name: "Qingming Festival", start: (2018, 4, 5), end: (2018, 4, 5) },
+            Period { name: "Labour Day", start: (2018, 5, 1), end: (2018, 5, 1) }
+        ],
+        makeup_days: &[],
+        milestones: &[
+            Period { name: "Examinations", start: (2018, 5, 28), end: (2018, 6, 8) }
+        ]
+    },
+    Semester {
+        id: "2025-26-s1",
+        name: "2025-26 Semester 1",
+        start: (2025, 9, 14),
+        end: (2025, 12, 28),
+        holidays: &[
+            Period { name: "National Day and Mid-Autumn Festival", start: (2025, 9, 29), end: (2025, 10, 8) }
+        ],
+        makeup_days: &[
+            // Working days in exchange for the National Day holiday
+            MakeupDay { date: (2025, 9, 28), weekday: 0, week: 3 },
+            MakeupDay { date: (2025, 10, 11), weekday: 1, week: 3 }
+        ],
+        milestones: &[
+            Period { name: "Examinations", start: (2026, 1, 5), end: (2026, 1, 16) }
+        ]
+    },
+    Semester {
+        id: "2025-26-s2",
+        name: "2025-26 Semester 2",
+        start: (2026, 3, 1),
+        end: (2026, 6, 7),
+        holidays: &[
+            Period { name: "Qingming Festival", start: (2026, 4, 4), end: (2026, 4, 6) },
+            Period { name: "Labour Day", start: (2026, 5, 1), end: (2026, 5, 5) }
+        ],
+        makeup_days: &[
+            // Working day in exchange for the Labour Day holiday
+            MakeupDay { date: (2026, 5, 9), weekday: 0, week: 10 }
+        ],
+        milestones: &[
+            Period { name: "Examinations", start: (2026, 6, 8), end: (2026, 6, 19) }
+        ]
+    },
+    Semester {
+        id: "2025-26-summer",
+        name: "2025-26 Summer",
+        start: (2026, 6, 28),
+        end: (2026, 8, 9),
+        holidays: &[],
+        makeup_days: &[],
+        milestones: &[
+            Period { name: "Examinations", start: (2026, 8, 10), end: (2026, 8, 14) }
+        ]
+    }
 ];
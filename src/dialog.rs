@@ -0,0 +1,152 @@
+/*
+ * Modal dialogs, using the markup and styles of Bootstrap modals
+ * but without its scripts (and thus jQuery and Popper).
+ * Showing and hiding, the backdrop, keeping the focus inside
+ * the dialog and closing it with Escape are all done here.
+ */
+use std::cell::RefCell;
+use util::{self, document};
+use wasm_bindgen::JsCast;
+use web_sys::{Element, Event, HtmlElement, KeyboardEvent};
+
+// Elements that can receive the focus by Tab
+const FOCUSABLE: &str = "a[href], button:not([disabled]), input:not([disabled]), \
+    select:not([disabled]), textarea:not([disabled]), [tabindex]:not([tabindex='-1'])";
+
+thread_local! {
+    // The element having the focus before the open dialog was shown
+    static RETURN_FOCUS: RefCell<Option<HtmlElement>> = const { RefCell::new(None) };
+}
+
+pub trait Dialog {
+    fn get_element(&self) -> &Element;
+
+    fn show(&self) {
+        show(self.get_element());
+    }
+}
+
+/*
+ * Make `elem` behave like a dialog
+ * Must be called once for every dialog element.
+ */
+pub fn init(elem: &Element) {
+    let dialog = elem.clone();
+    util::add_event_listener(elem, "click", move |ev: Event| {
+        let target = match ev.target().and_then(|t| t.dyn_into::<Element>().ok()) {
+            Some(target) => target,
+            None => return
+        };
+        // The dialog element itself is only visible around the content,
+        // so clicking on it means clicking on the backdrop
+        let dismiss = target.closest("[data-dismiss='modal']").ok().and_then(|e| e).is_some();
+        if target == dialog || dismiss {
+            ev.prevent_default();
+            hide(&dialog);
+        }
+    });
+
+    let dialog = elem.clone();
+    util::add_event_listener(elem, "keydown", move |ev: Event| {
+        let ev = match ev.dyn_into::<KeyboardEvent>() {
+            Ok(ev) => ev,
+            Err(_) => return
+        };
+        match ev.key().as_str() {
+            "Escape" | "Esc" => {
+                ev.prevent_default();
+                hide(&dialog);
+            },
+            "Tab" => trap_focus(&dialog, &ev),
+            _ => ()
+        }
+    });
+}
+
+fn is_shown(elem: &Element) -> bool {
+    elem.class_list().contains("show")
+}
+
+fn show(elem: &Element) {
+    if is_shown(elem) {
+        return;
+    }
+
+    let document = document();
+    let body = document.body().unwrap();
+    let backdrop = document.create_element("div").unwrap();
+    backdrop.set_class_name("modal-backdrop fade show");
+    body.append_child(&backdrop).unwrap();
+    body.class_list().add_1("modal-open").unwrap();
+
+    elem.set_attribute("style", "display: block").unwrap();
+    elem.class_list().add_1("show").unwrap();
+    elem.remove_attribute("aria-hidden").unwrap();
+    elem.set_attribute("aria-modal", "true").unwrap();
+
+    let active = document.active_element().and_then(|e| e.dyn_into::<HtmlElement>().ok());
+    RETURN_FOCUS.with(|f| *f.borrow_mut() = active);
+    focus_first(elem);
+}
+
+fn hide(elem: &Element) {
+    if !is_shown(elem) {
+        return;
+    }
+
+    elem.class_list().remove_1("show").unwrap();
+    elem.set_attribute("style", "display: none").unwrap();
+    elem.set_attribute("aria-hidden", "true").unwrap();
+    elem.remove_attribute("aria-modal").unwrap();
+
+    let document = document();
+    if let Some(backdrop) = document.query_selector(".modal-backdrop").unwrap() {
+        backdrop.remove();
+    }
+    document.body().unwrap().class_list().remove_1("modal-open").unwrap();
+
+    // Give the focus back to where it was, e.g. the button opening the dialog
+    if let Some(elem) = RETURN_FOCUS.with(|f| f.borrow_mut().take()) {
+        let _ = elem.focus();
+    }
+}
+
+fn focusable_elements(elem: &Element) -> Vec<HtmlElement> {
+    let list = elem.query_selector_all(FOCUSABLE).unwrap();
+    (0..list.length())
+        .filter_map(|i| list.item(i))
+        .filter_map(|n| n.dyn_into::<HtmlElement>().ok())
+        .collect()
+}
+
+fn focus_first(elem: &Element) {
+    match focusable_elements(elem).first() {
+        Some(first) => { let _ = first.focus(); },
+        None => if let Some(elem) = elem.dyn_ref::<HtmlElement>() {
+            let _ = elem.focus();
+        }
+    }
+}
+
+// Wrap around at either end when moving the focus with Tab
+fn trap_focus(elem: &Element, ev: &KeyboardEvent) {
+    let focusable = focusable_elements(elem);
+    let (first, last) = match (focusable.first(), focusable.last()) {
+        (Some(first), Some(last)) => (first, last),
+        _ => {
+            // Nothing to move the focus to
+            ev.prevent_default();
+            return;
+        }
+    };
+
+    let active = document().active_element();
+    let inside = active.as_ref().is_some_and(|a| elem.contains(Some(a)) && a != elem);
+    if ev.shift_key() && (!inside || active.as_ref() == Some(first.as_ref())) {
+        ev.prevent_default();
+        let _ = last.focus();
+    } else if !ev.shift_key() && (!inside || active.as_ref() == Some(last.as_ref())) {
+        ev.prevent_default();
+        let _ = first.focus();
+    }
+}
@@ -0,0 +1,217 @@
+/*
+ * Compare two versions of a calendar, e.g. a previously
+ * exported one against the current E-Bridge timetable,
+ * report what has changed and build update calendars
+ * that calendar apps can apply to the events they already have.
+ */
+use chrono::Datelike;
+use ical::{ICalBuilder, ICalEvent};
+use std::fmt;
+
+// Updates must name their organizer (RFC 5546), which is the exporter itself
+const ORGANIZER: &str = "mailto:timetable@xjtlu-timetable.invalid";
+
+// There are only a few changes at a time, their size does not matter
+#[allow(clippy::large_enum_variant)]
+pub enum Change {
+    Added(ICalEvent),
+    Removed(ICalEvent),
+    Moved(ICalEvent, ICalEvent) // (old, new)
+}
+
+impl Change {
+    // The time used to sort the changes in the report
+    fn sort_key(&self) -> i64 {
+        match *self {
+            Change::Added(ref e) | Change::Removed(ref e) | Change::Moved(ref e, _) => e.start.timestamp()
+        }
+    }
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Change::Added(ref e) => write!(f, "+ {}", describe(e)),
+            Change::Removed(ref e) => write!(f, "- {}", describe(e)),
+            Change::Moved(ref old, ref new) => write!(f, "~ {}\n  -> {}", describe(old), describe(new))
+        }
+    }
+}
+
+// What happened to an event of the old calendar
+enum Pairing {
+    Same(usize), // Still in the new calendar at the index
+    Moved(usize), // Replaced by the event of the new calendar at the index
+    Removed
+}
+
+/*
+ * Pair up the events of two calendars
+ * Events that only exist in one of them are added or removed,
+ * unless an event with the same title was removed and added
+ * in the same week, which is considered as a moved event instead.
+ * Returns the pairing of each old event, and whether each new event
+ * has been paired with an old one.
+ */
+fn pair_events(old: &[ICalEvent], new: &[ICalEvent]) -> (Vec<Pairing>, Vec<bool>) {
+    let mut matched = vec![false; new.len()];
+    let mut pairings: Vec<Pairing> = old.iter()
+        .map(|event| {
+            let same = new.iter().enumerate()
+                .position(|(i, e)| !matched[i] && e.same_content(event));
+            match same {
+                Some(i) => {
+                    matched[i] = true;
+                    Pairing::Same(i)
+                },
+                None => Pairing::Removed
+            }
+        })
+        .collect();
+
+    for (event, pairing) in old.iter().zip(pairings.iter_mut()) {
+        if let Pairing::Removed = *pairing {
+            let moved = new.iter().enumerate()
+                .position(|(i, e)| !matched[i] && e.summary == event.summary && same_week(e, event));
+            if let Some(i) = moved {
+                matched[i] = true;
+                *pairing = Pairing::Moved(i);
+            }
+        }
+    }
+
+    (pairings, matched)
+}
+
+/*
+ * Find the differences between the events of two calendars
+ * The new event of a moved one takes over the UID of the old event
+ * with its SEQUENCE increased, so that it can be sent as an update.
+ */
+pub fn diff_events(old: &[ICalEvent], new: &[ICalEvent]) -> Vec<Change> {
+    let (pairings, matched) = pair_events(old, new);
+
+    let mut changes = Vec::new();
+    for (event, pairing) in old.iter().zip(pairings.iter()) {
+        match *pairing {
+            Pairing::Same(_) => (),
+            Pairing::Moved(i) if event.uid.is_empty() => changes.push(Change::Moved(event.clone(), new[i].clone())),
+            Pairing::Moved(i) => changes.push(Change::Moved(event.clone(), ICalEvent {
+                uid: event.uid.clone(),
+                sequence: event.sequence + 1,
+                ..new[i].clone()
+            })),
+            Pairing::Removed => changes.push(Change::Removed(event.clone()))
+        }
+    }
+
+    for (i, event) in new.iter().enumerate() {
+        if !matched[i] {
+            changes.push(Change::Added(event.clone()));
+        }
+    }
+
+    changes.sort_by_key(|c| c.sort_key());
+    changes
+}
+
+/*
+ * Let the events of a new calendar keep the UIDs (and revisions)
+ * of their counterparts in the old one, so that the new calendar
+ * can be compared against again after the update has been applied.
+ */
+pub fn inherit_uids(old: &[ICalEvent], new: &mut [ICalEvent]) {
+    let (pairings, _) = pair_events(old, new);
+    for (event, pairing) in old.iter().zip(pairings.iter()) {
+        let (i, sequence) = match *pairing {
+            Pairing::Same(i) => (i, event.sequence),
+            Pairing::Moved(i) => (i, event.sequence + 1),
+            Pairing::Removed => continue
+        };
+        if event.uid.is_empty() {
+            continue;
+        }
+        new[i].uid = event.uid.clone();
+        new[i].sequence = sequence;
+    }
+}
+
+/*
+ * Build the calendar updating the moved and added events (METHOD:REQUEST)
+ */
+pub fn changes_to_request(changes: &[Change]) -> ICalBuilder {
+    let mut builder = ICalBuilder::with_method("REQUEST");
+    for change in changes {
+        match *change {
+            Change::Added(ref e) | Change::Moved(_, ref e) => builder.add(ICalEvent {
+                organizer: Some(ORGANIZER.to_string()),
+                ..e.clone()
+            }),
+            Change::Removed(_) => ()
+        }
+    }
+    builder
+}
+
+/*
+ * Build the calendar cancelling the removed events (METHOD:CANCEL)
+ */
+pub fn changes_to_cancel(changes: &[Change]) -> ICalBuilder {
+    let mut builder = ICalBuilder::with_method("CANCEL");
+    for change in changes {
+        if let Change::Removed(ref e) = *change {
+            builder.add(ICalEvent {
+                sequence: e.sequence + 1,
+                cancelled: true,
+                organizer: Some(ORGANIZER.to_string()),
+                ..e.clone()
+            });
+        }
+    }
+    builder
+}
+
+/*
+ * Whether some of the changed events were read without a UID,
+ * i.e. from a calendar exported before events had stable UIDs.
+ * Calendar apps have given such events UIDs of their own,
+ * so the update and cancellation calendars cannot reach them.
+ */
+pub fn has_unknown_uids(changes: &[Change]) -> bool {
+    changes.iter().any(|c| match *c {
+        Change::Removed(ref e) | Change::Moved(ref e, _) => e.uid.is_empty(),
+        Change::Added(_) => false
+    })
+}
+
+/*
+ * Format the changes into a human-readable report
+ */
+pub fn format_report(changes: &[Change]) -> String {
+    if changes.is_empty() {
+        return "No changes found.\n".to_string();
+    }
+
+    let mut report: String = changes.iter()
+        .map(|c| format!("{}\n", c))
+        .collect();
+    if has_unknown_uids(changes) {
+        report.push_str("The previous calendar was exported by an older version without event UIDs, \
+            so the changed classes cannot be updated or cancelled in place. \
+            Please remove the previous calendar and import the new one instead.\n");
+    }
+    report
+}
+
+fn same_week(a: &ICalEvent, b: &ICalEvent) -> bool {
+    a.start.iso_week() == b.start.iso_week()
+}
+
+fn describe(e: &ICalEvent) -> String {
+    format!("{} {}-{} {} ({})",
+        e.start.format("%a %Y-%m-%d"),
+        e.start.format("%H:%M"),
+        e.end.format("%H:%M"),
+        e.summary,
+        e.location)
+}
@@ -0,0 +1,170 @@
+/*
+ * Find the times at which everyone of a group is free,
+ * e.g. to schedule the meetings of a group project
+ */
+use chrono::{Date, DateTime, Datelike, FixedOffset};
+use class::{Class, ClassTime};
+use ical::{event_uid, ICalBuilder, ICalEvent};
+use occurrence::{occurrences, Occurrence};
+use semester::Semester;
+
+const WEEKDAYS: &[&str] = &["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+
+pub struct FreeSlotOptions {
+    pub day_start: ClassTime, // Meetings start no earlier than this
+    pub day_end: ClassTime, // Meetings end no later than this
+    pub min_len: usize, // Shortest slot worth a meeting, in half-hours
+    pub weekends: bool // Also look at Saturdays and Sundays, other than make-up days which are always looked at
+}
+
+impl Default for FreeSlotOptions {
+    fn default() -> FreeSlotOptions {
+        FreeSlotOptions {
+            day_start: ClassTime { hour: 9, half: false },
+            day_end: ClassTime { hour: 18, half: false },
+            min_len: 2,
+            weekends: false
+        }
+    }
+}
+
+/*
+ * A period during which none of the timetables has a class
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub struct FreeSlot {
+    pub week: usize,
+    pub date: Date<FixedOffset>,
+    pub start: DateTime<FixedOffset>,
+    pub end: DateTime<FixedOffset>
+}
+
+/*
+ * The common free time of the timetables on the class days of the semester
+ * Holidays are left out, since people are likely to be away.
+ */
+pub fn find_free_slots(timetables: &[Vec<Class>], semester: &Semester, options: &FreeSlotOptions) -> Vec<FreeSlot> {
+    let first = options.day_start.half_hours();
+    let last = options.day_end.half_hours();
+    if last <= first {
+        return Vec::new();
+    }
+
+    let mut sessions: Vec<Occurrence> = timetables.iter()
+        .flat_map(|classes| occurrences(classes, semester))
+        .collect();
+    sessions.sort_by_key(|o| o.start);
+
+    let mut ret = Vec::new();
+    for day in semester.class_days() {
+        // Make-up days are working days, even on weekends
+        if !options.weekends && day.date.weekday().num_days_from_monday() >= 5
+            && semester.makeup_day(&day.date).is_none() {
+            continue;
+        }
+
+        // Mark the busy half-hours of the day
+        let mut busy = vec![false; last - first];
+        for o in sessions.iter().filter(|o| o.date == day.date) {
+            let start = o.class.start.half_hours().max(first);
+            let end = o.class.end_half_hours().min(last);
+            for slot in start..end.max(start) {
+                busy[slot - first] = true;
+            }
+        }
+
+        // Then collect the runs of free half-hours
+        let mut slot = 0;
+        while slot < busy.len() {
+            if busy[slot] {
+                slot += 1;
+                continue;
+            }
+            let start = slot;
+            while slot < busy.len() && !busy[slot] {
+                slot += 1;
+            }
+            if slot - start >= options.min_len.max(1) {
+                ret.push(FreeSlot {
+                    week: day.week,
+                    date: day.date,
+                    start: at(&day.date, first + start),
+                    end: at(&day.date, first + slot)
+                });
+            }
+        }
+    }
+    ret
+}
+
+fn at(date: &Date<FixedOffset>, half_hours: usize) -> DateTime<FixedOffset> {
+    let time = ClassTime::from_half_hours(half_hours);
+    if time.hour >= 24 {
+        return date.succ().and_hms(0, 0, 0);
+    }
+    date.and_hms(time.hour as u32, if time.half { 30 } else { 0 }, 0)
+}
+
+/*
+ * The free slots as a table, one line per day grouped by weeks
+ */
+pub fn format_free_slots(slots: &[FreeSlot]) -> String {
+    if slots.is_empty() {
+        return "No common free time found.\n".to_string();
+    }
+
+    let mut ret = String::new();
+    let mut i = 0;
+    while i < slots.len() {
+        let date = slots[i].date;
+        if i == 0 || slots[i - 1].week != slots[i].week {
+            ret.push_str(&format!("Week {}\n", slots[i].week));
+        }
+
+        let times: Vec<String> = slots[i..].iter()
+            .take_while(|s| s.date == date)
+            .map(|s| format!("{}-{}", s.start.format("%H:%M"), format_end(s)))
+            .collect();
+        ret.push_str(&format!(
+            "  {} {}  {}\n",
+            WEEKDAYS[date.weekday().num_days_from_monday() as usize],
+            date.format("%Y-%m-%d"),
+            times.join(", ")
+        ));
+        i += times.len();
+    }
+    ret
+}
+
+// Slots lasting until midnight end at 24:00 rather than 00:00
+fn format_end(slot: &FreeSlot) -> String {
+    if slot.end.date() != slot.date {
+        "24:00".to_string()
+    } else {
+        slot.end.format("%H:%M").to_string()
+    }
+}
+
+/*
+ * The free slots as candidate meetings, to be imported into a calendar
+ */
+pub fn free_slots_to_ical(slots: &[FreeSlot], people: usize) -> ICalBuilder {
+    let mut builder = ICalBuilder::new();
+    for slot in slots {
+        let summary = "Free for meeting".to_string();
+        builder.add(ICalEvent {
+            uid: event_uid(&summary, &[], &slot.start),
+            sequence: 0,
+            summary,
+            start: slot.start,
+            end: slot.end,
+            location: "".to_string(),
+            description: format!("All {} timetables are free (week {})", people, slot.week),
+            color: None,
+            categories: Vec::new(),
+            cancelled: false,
+            organizer: None
+        });
+    }
+    builder
+}
@@ -0,0 +1,11 @@
+/*
+ * FNV-1a hash
+ * Used wherever a value derived from a string has to stay the same
+ * across exports (e.g. UIDs and colors), which the std hasher
+ * does not guarantee.
+ */
+pub fn fnv1a(s: &str) -> u64 {
+    s.bytes().fold(0xcbf29ce484222325u64, |h, b| {
+        (h ^ (b as u64)).wrapping_mul(0x100000001b3)
+    })
+}
@@ -0,0 +1,384 @@
+/*
+ * Simple and naive HTML parser
+ * It only knows enough HTML to read the tables of E-Bridge pages,
+ * either pasted into the web page or saved as a file,
+ * so that the timetable can be parsed without a browser.
+ */
+
+#[derive(Clone, Debug)]
+pub enum Node {
+    Element(Element),
+    Text(String)
+}
+
+#[derive(Clone, Debug)]
+pub struct Element {
+    pub name: String, // Lowercase tag name
+    pub attrs: Vec<(String, String)>,
+    pub children: Vec<Node>
+}
+
+// Elements that never have any content or end tag
+const VOID_ELEMENTS: &[&str] = &[
+    "area", "base", "br", "col", "embed", "hr", "img", "input",
+    "link", "meta", "param", "source", "track", "wbr"
+];
+
+// Elements whose content is not HTML
+const RAW_TEXT_ELEMENTS: &[&str] = &["script", "style", "textarea", "title"];
+
+// Elements starting a new line in the text of a page
+const BLOCK_ELEMENTS: &[&str] = &[
+    "address", "article", "aside", "blockquote", "div", "dl", "dt", "dd", "footer",
+    "h1", "h2", "h3", "h4", "h5", "h6", "header", "hr", "li", "ol", "p", "pre",
+    "section", "table", "tr", "ul"
+];
+
+const TABLE_ELEMENTS: &[&str] = &["table", "thead", "tbody", "tfoot", "tr"];
+
+// Deepest nesting of elements, as in browsers the content of deeper
+// ones goes into the deepest element instead. This bounds the recursion
+// of walking and dropping the tree, whatever the markup is.
+const MAX_DEPTH: usize = 256;
+
+impl Element {
+    fn new(name: &str, attrs: Vec<(String, String)>) -> Element {
+        Element { name: name.to_string(), attrs, children: Vec::new() }
+    }
+
+    pub fn attr(&self, name: &str) -> Option<&str> {
+        self.attrs.iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v.as_str())
+    }
+
+    pub fn has_class(&self, class: &str) -> bool {
+        self.attr("class")
+            .is_some_and(|c| c.split_whitespace().any(|c| c == class))
+    }
+
+    // Whether this is a `name` element, with the class `class` if given
+    pub fn is(&self, name: &str, class: Option<&str>) -> bool {
+        self.name == name && class.is_none_or(|c| self.has_class(c))
+    }
+
+    // Child elements, without the text nodes
+    pub fn children(&self) -> Vec<&Element> {
+        self.children.iter()
+            .filter_map(|n| match *n {
+                Node::Element(ref e) => Some(e),
+                Node::Text(_) => None
+            })
+            .collect()
+    }
+
+    // All the elements inside this one, in document order
+    pub fn descendants(&self) -> Vec<&Element> {
+        let mut ret = Vec::new();
+        for child in self.children() {
+            ret.push(child);
+            ret.append(&mut child.descendants());
+        }
+        ret
+    }
+
+    // Like `querySelectorAll("name.class")`
+    pub fn select(&self, name: &str, class: Option<&str>) -> Vec<&Element> {
+        self.descendants().into_iter()
+            .filter(|e| e.is(name, class))
+            .collect()
+    }
+
+    pub fn text_content(&self) -> String {
+        let mut ret = String::new();
+        for child in &self.children {
+            match *child {
+                Node::Element(ref e) => ret.push_str(&e.text_content()),
+                Node::Text(ref t) => ret.push_str(t)
+            }
+        }
+        ret
+    }
+
+    /*
+     * The text as a browser would copy it: block elements and `br`
+     * start new lines, and the cells of a table row are separated by tabs
+     */
+    pub fn inner_text(&self) -> String {
+        let mut ret = String::new();
+        self.append_inner_text(&mut ret);
+        ret
+    }
+
+    fn append_inner_text(&self, ret: &mut String) {
+        if ["head", "script", "style", "title"].contains(&self.name.as_str()) {
+            return;
+        }
+
+        let block = BLOCK_ELEMENTS.contains(&self.name.as_str());
+        if block && !ret.is_empty() && !ret.ends_with('\n') {
+            ret.push('\n');
+        }
+
+        let mut first_cell = true;
+        for child in &self.children {
+            match *child {
+                Node::Element(ref e) if e.name == "br" => ret.push('\n'),
+                Node::Element(ref e) => {
+                    if e.name == "td" || e.name == "th" {
+                        if !first_cell {
+                            ret.push('\t');
+                        }
+                        first_cell = false;
+                    }
+                    e.append_inner_text(ret);
+                },
+                // Whitespace between the tags of a table is not part of any cell
+                Node::Text(ref t) if TABLE_ELEMENTS.contains(&self.name.as_str()) && t.trim().is_empty() => (),
+                Node::Text(ref t) => ret.push_str(t)
+            }
+        }
+
+        if block && !ret.ends_with('\n') {
+            ret.push('\n');
+        }
+    }
+
+    // A numeric attribute such as `colspan`
+    pub fn attr_usize(&self, name: &str) -> Option<usize> {
+        self.attr(name).and_then(|v| v.trim().parse().ok())
+    }
+}
+
+/*
+ * Parse an HTML document or fragment
+ * The result is a root element named `#document` containing everything.
+ * This never fails: like browsers, unknown or broken markup is
+ * skipped or closed implicitly.
+ */
+pub fn parse_html(html: &str) -> Element {
+    let mut stack = vec![Element::new("#document", Vec::new())];
+    let mut rest = html;
+
+    while !rest.is_empty() {
+        if rest.starts_with("<!--") {
+            rest = skip_past(rest, "-->");
+        } else if rest.starts_with("<![CDATA[") {
+            // Only found in XML, e.g. the responses of CalDAV servers
+            let end = rest.find("]]>").unwrap_or(rest.len());
+            push_text(&mut stack, rest[9..end].to_string());
+            rest = skip_past(rest, "]]>");
+        } else if rest.starts_with("<!") || rest.starts_with("<?") {
+            rest = skip_past(rest, ">");
+        } else if rest.starts_with("</") {
+            let (name, after) = read_name(&rest[2..]);
+            rest = skip_past(after, ">");
+            if !name.is_empty() {
+                close_element(&mut stack, &name);
+            }
+        } else if rest.starts_with('<') && rest[1..].starts_with(|c: char| c.is_ascii_alphabetic()) {
+            let (name, after) = read_name(&rest[1..]);
+            let (attrs, self_closing, after) = read_attributes(after);
+            rest = after;
+            let opened = open_element(&mut stack, &name, attrs);
+
+            if self_closing || VOID_ELEMENTS.contains(&name.as_str()) {
+                if opened {
+                    close_element(&mut stack, &name);
+                }
+            } else if RAW_TEXT_ELEMENTS.contains(&name.as_str()) {
+                let end = find_ignore_case(rest, &format!("</{}", name)).unwrap_or(rest.len());
+                push_text(&mut stack, decode_entities(&rest[..end]));
+                rest = &rest[end..];
+            }
+        } else {
+            // Text until the next tag, which may start with a `<` not opening one
+            let first = rest.chars().next().map_or(1, |c| c.len_utf8());
+            let end = rest[first..].find('<').map(|i| i + first).unwrap_or(rest.len());
+            push_text(&mut stack, decode_entities(&rest[..end]));
+            rest = &rest[end..];
+        }
+    }
+
+    while stack.len() > 1 {
+        pop_element(&mut stack);
+    }
+    stack.pop().unwrap()
+}
+
+// Returns whether the element was opened, i.e. not too deep
+fn open_element(stack: &mut Vec<Element>, name: &str, attrs: Vec<(String, String)>) -> bool {
+    // Table parts and list items close the previous ones implicitly,
+    // but never across the table or list containing them
+    let closes: &[&str] = match name {
+        "td" | "th" => &["td", "th"],
+        "tr" => &["td", "th", "tr"],
+        "tbody" | "thead" | "tfoot" => &["td", "th", "tr", "tbody", "thead", "tfoot"],
+        "li" => &["li"],
+        "p" => &["p"],
+        _ => &[]
+    };
+    if !closes.is_empty() {
+        // Close from the outermost of them, e.g. a `tr` closes the previous
+        // row along with its cell
+        let boundary = ["table", "ul", "ol", "#document"];
+        let start = stack.iter()
+            .rposition(|e| boundary.contains(&e.name.as_str()))
+            .map_or(0, |i| i + 1);
+        let open = stack[start..].iter()
+            .position(|e| closes.contains(&e.name.as_str()));
+        if let Some(i) = open {
+            while stack.len() > start + i {
+                pop_element(stack);
+            }
+        }
+    }
+    if stack.len() > MAX_DEPTH {
+        return false;
+    }
+    stack.push(Element::new(name, attrs));
+    true
+}
+
+// Close the innermost open element named `name`, if any
+fn close_element(stack: &mut Vec<Element>, name: &str) {
+    if let Some(i) = stack.iter().rposition(|e| e.name == name) {
+        if i == 0 {
+            return;
+        }
+        while stack.len() > i {
+            pop_element(stack);
+        }
+    }
+}
+
+fn pop_element(stack: &mut Vec<Element>) {
+    let elem = stack.pop().unwrap();
+    stack.last_mut().unwrap().children.push(Node::Element(elem));
+}
+
+fn push_text(stack: &mut [Element], text: String) {
+    if text.is_empty() {
+        return;
+    }
+    let parent = stack.last_mut().unwrap();
+    if let Some(&mut Node::Text(ref mut last)) = parent.children.last_mut() {
+        last.push_str(&text);
+        return;
+    }
+    parent.children.push(Node::Text(text));
+}
+
+fn skip_past<'a>(s: &'a str, end: &str) -> &'a str {
+    s.find(end).map(|i| &s[(i + end.len())..]).unwrap_or("")
+}
+
+fn find_ignore_case(s: &str, needle: &str) -> Option<usize> {
+    s.to_ascii_lowercase().find(needle)
+}
+
+// A tag or attribute name, lowercased
+fn read_name(s: &str) -> (String, &str) {
+    let end = s.find(|c: char| c.is_whitespace() || c == '/' || c == '>' || c == '=')
+        .unwrap_or(s.len());
+    (s[..end].to_ascii_lowercase(), &s[end..])
+}
+
+// The attributes of a start tag, and whether it ends with `/>`
+fn read_attributes(s: &str) -> (Vec<(String, String)>, bool, &str) {
+    let mut attrs = Vec::new();
+    let mut rest = s;
+    loop {
+        rest = rest.trim_start();
+        if rest.is_empty() {
+            return (attrs, false, rest);
+        } else if let Some(after) = rest.strip_prefix("/>") {
+            return (attrs, true, after);
+        } else if let Some(after) = rest.strip_prefix('>') {
+            return (attrs, false, after);
+        } else if let Some(after) = rest.strip_prefix('/') {
+            rest = after;
+            continue;
+        }
+
+        let (name, after) = read_name(rest);
+        rest = after.trim_start();
+        if name.is_empty() {
+            // Stray `=` or the like
+            rest = &rest[rest.chars().next().map_or(0, |c| c.len_utf8())..];
+            continue;
+        }
+
+        let mut value = String::new();
+        if rest.starts_with('=') {
+            rest = rest[1..].trim_start();
+            let quote = rest.chars().next();
+            if quote == Some('"') || quote == Some('\'') {
+                let q = quote.unwrap();
+                let end = rest[1..].find(q).map(|i| i + 1).unwrap_or(rest.len());
+                value = decode_entities(&rest[1..end]);
+                rest = if end < rest.len() { &rest[(end + 1)..] } else { "" };
+            } else {
+                let end = rest.find(|c: char| c.is_whitespace() || c == '>').unwrap_or(rest.len());
+                value = decode_entities(&rest[..end]);
+                rest = &rest[end..];
+            }
+        }
+        attrs.push((name, value));
+    }
+}
+
+/*
+ * Replace character references such as `&amp;` and `&#20013;`
+ * Only the most common named references are known.
+ */
+pub fn decode_entities(s: &str) -> String {
+    if !s.contains('&') {
+        return s.to_string();
+    }
+
+    let mut ret = String::new();
+    let mut rest = s;
+    while let Some(i) = rest.find('&') {
+        ret.push_str(&rest[..i]);
+        rest = &rest[i..];
+        let end = match rest.find(';') {
+            Some(end) if end <= 10 => end,
+            _ => {
+                ret.push('&');
+                rest = &rest[1..];
+                continue;
+            }
+        };
+
+        let entity = &rest[1..end];
+        let decoded = if let Some(hex) = entity.strip_prefix("#x").or_else(|| entity.strip_prefix("#X")) {
+            u32::from_str_radix(hex, 16).ok().and_then(::std::char::from_u32)
+        } else if let Some(dec) = entity.strip_prefix('#') {
+            dec.parse().ok().and_then(::std::char::from_u32)
+        } else {
+            match entity {
+                "amp" => Some('&'),
+                "lt" => Some('<'),
+                "gt" => Some('>'),
+                "quot" => Some('"'),
+                "apos" => Some('\''),
+                "nbsp" => Some('\u{a0}'),
+                _ => None
+            }
+        };
+
+        match decoded {
+            Some(c) => {
+                ret.push(c);
+                rest = &rest[(end + 1)..];
+            },
+            None => {
+                ret.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    ret.push_str(rest);
+    ret
+}
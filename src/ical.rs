@@ -3,111 +3,307 @@
  * only for use in this crate, so only GMT+8 (Asia/Shanghai)
  * can be processed correctly
  */
-use constants::*;
-use chrono::{Datelike, DateTime, Duration, Utc, FixedOffset, TimeZone};
-use parser::Class;
+use chrono::{Date, DateTime, FixedOffset};
+use class::Class;
+use color::ModuleColors;
+use hash::fnv1a;
+use occurrence::{occurrences, Occurrence};
+use semester::{current_semester, Period, Semester};
+
+/*
+ * Options controlling what goes into the exported calendar
+ */
+#[derive(Clone)]
+pub struct ExportOptions {
+    pub semester: &'static Semester,
+    pub colors: ModuleColors,
+    pub holidays: bool, // Include the holidays as all-day events
+    pub milestones: bool, // Include the start and end of the semester and other milestones as all-day events
+    pub week_numbers: bool // Mark each teaching week with an all-day "Week N" event on its Monday
+}
+
+impl Default for ExportOptions {
+    fn default() -> ExportOptions {
+        ExportOptions {
+            semester: current_semester(),
+            colors: ModuleColors::new(),
+            holidays: false,
+            milestones: false,
+            week_numbers: false
+        }
+    }
+}
 
 /*
  * Convert a list of XJTLU classes to ical file (.ics)
  */
-pub fn classes_to_ical(classes: &[Class]) -> ICalBuilder {
+pub fn classes_to_ical(classes: &[Class], options: &ExportOptions) -> ICalBuilder {
+    events_to_ical(classes_to_events(classes, options), options)
+}
+
+/*
+ * Build the calendar from the events of the classes,
+ * adding the informational events of the semester asked for in `options`
+ */
+pub fn events_to_ical(events: Vec<ICalEvent>, options: &ExportOptions) -> ICalBuilder {
     let mut builder = ICalBuilder::new();
-    let tz = offset_utc8();
-    let mut current_date = tz.ymd(
-        Utc::now().with_timezone(&tz).year(),
-        SEMESTER_START.0,
-        SEMESTER_START.1
-    );
-    let mut current_week = 0;
-    while current_date.month() != SEMESTER_END.0 || current_date.day() != SEMESTER_END.1 {
-        // Go to the next day
-        current_date = current_date.succ();
-        let day = current_date.weekday().num_days_from_monday();
-
-        if day == 0 {
-            if VACATION_WEEKS.contains(&(current_date.month(), current_date.day())) {
-                for _ in 0..7 {
-                    current_date = current_date.succ();
-                }
-            } else {
-                current_week += 1;
-            }
+    for event in events {
+        builder.add(event);
+    }
+
+    let semester = options.semester;
+    if options.holidays {
+        for holiday in semester.holidays {
+            builder.add(period_event(holiday, "No classes"));
+        }
+    }
+
+    if options.milestones {
+        let start = semester.first_date();
+        let end = semester.end_date();
+        builder.add(ICalAllDayEvent {
+            uid: all_day_uid("Semester starts", &start),
+            summary: format!("{} starts", semester.name),
+            start,
+            end: start.succ(),
+            description: semester.name.to_string()
+        });
+        builder.add(ICalAllDayEvent {
+            uid: all_day_uid("Semester ends", &end),
+            summary: format!("{} ends", semester.name),
+            start: end,
+            end: end.succ(),
+            description: semester.name.to_string()
+        });
+        for milestone in semester.milestones {
+            builder.add(period_event(milestone, semester.name));
         }
+    }
 
-        // Skip vacations
-        if VACATION_DAYS.contains(&(current_date.month(), current_date.day())) {
-            continue;
+    if options.week_numbers {
+        for week in semester.teaching_weeks() {
+            // The first week may start before the semester does
+            let day = week.monday.max(semester.first_date());
+            let summary = format!("Week {}", week.week);
+            builder.add(ICalAllDayEvent {
+                uid: all_day_uid(&summary, &day),
+                summary,
+                start: day,
+                end: day.succ(),
+                description: semester.name.to_string()
+            });
         }
+    }
+    builder
+}
 
-        for class in classes {
-            if !class.weeks[current_week - 1] || class.day != (day as usize) {
-                continue;
-            }
+fn period_event(period: &Period, description: &str) -> ICalAllDayEvent {
+    ICalAllDayEvent {
+        uid: all_day_uid(period.name, &period.start_date()),
+        summary: period.name.to_string(),
+        start: period.start_date(),
+        end: period.end_date().succ(),
+        description: description.to_string()
+    }
+}
 
-            let min = if class.start.half { 30 } else { 0 };
-            let start = current_date.and_hms(class.start.hour as u32, min, 0);
-            let end = start + Duration::minutes((class.len as i64) * 30);
-
-            builder.add(ICalEvent {
-                summary: class.title.clone(),
-                start,
-                end,
-                location: class.location.clone(),
-                description: format!("by {}", class.lecturer)
-            })
+/*
+ * Expand a list of XJTLU classes into the concrete events
+ * that happen during the selected semester
+ * When the classes come from several timetables (see `Class::owner`),
+ * each event names its owners, and a session attended by several
+ * of them becomes a single event listing all of them.
+ */
+pub fn classes_to_events(classes: &[Class], options: &ExportOptions) -> Vec<ICalEvent> {
+    // Sessions along with their owners, in order of their first appearance
+    let mut sessions: Vec<(Occurrence, Vec<&str>)> = Vec::new();
+    for o in occurrences(classes, options.semester) {
+        let owner = o.class.owner.as_deref();
+        // Sessions on the same day are next to each other
+        let same = sessions.iter_mut().rev()
+            .take_while(|&&mut (ref other, _)| other.date == o.date)
+            .find(|&&mut (ref other, _)| same_session(other, &o));
+        match same {
+            Some(&mut (_, ref mut owners)) => {
+                if let Some(owner) = owner {
+                    if !owners.contains(&owner) {
+                        owners.push(owner);
+                    }
+                }
+            },
+            None => sessions.push((o, owner.into_iter().collect()))
         }
     }
-    return builder;
+
+    sessions.into_iter()
+        .map(|(o, owners)| {
+            let mut summary = o.class.title.clone();
+            let mut description = format!("by {}", o.class.lecturer);
+            let mut categories = vec![o.class.module_code().to_string()];
+            if !owners.is_empty() {
+                summary.push_str(&format!(" ({})", owners.join(", ")));
+                description.push_str(&format!("; attended by {}", owners.join(", ")));
+                categories.extend(owners.iter().map(|s| s.to_string()));
+            }
+
+            ICalEvent {
+                uid: event_uid(&summary, &[&o.class.location, &o.class.lecturer], &o.start),
+                sequence: 0,
+                summary,
+                start: o.start,
+                end: o.end,
+                location: o.class.location.clone(),
+                description,
+                color: Some(options.colors.get(o.class.module_code()).to_string()),
+                categories,
+                cancelled: false,
+                organizer: None
+            }
+        })
+        .collect()
+}
+
+// Whether two sessions are the same one, possibly from different timetables
+fn same_session(a: &Occurrence, b: &Occurrence) -> bool {
+    a.start == b.start && a.end == b.end && a.class.title == b.class.title
+        && a.class.location == b.class.location && a.class.lecturer == b.class.lecturer
 }
 
 pub trait ICalElement {
     fn serialize(&self) -> String;
 }
 
+#[derive(Clone, Debug, PartialEq)]
 pub struct ICalEvent {
-    summary: String,
-    start: DateTime<FixedOffset>,
-    end: DateTime<FixedOffset>,
-    location: String,
-    description: String
+    pub uid: String,
+    pub sequence: u32, // Revision of the event, increased on each update
+    pub summary: String,
+    pub start: DateTime<FixedOffset>,
+    pub end: DateTime<FixedOffset>,
+    pub location: String,
+    pub description: String,
+    pub color: Option<String>, // CSS3 color name (RFC 7986)
+    pub categories: Vec<String>,
+    pub cancelled: bool,
+    pub organizer: Option<String> // Calendar address, required by METHOD:REQUEST and CANCEL
+}
+
+impl ICalEvent {
+    /*
+     * Whether two events describe the same class session,
+     * regardless of their UIDs and revisions
+     */
+    pub fn same_content(&self, other: &ICalEvent) -> bool {
+        self.summary == other.summary && self.start == other.start && self.end == other.end
+            && self.location == other.location && self.description == other.description
+    }
 }
 
 impl ICalElement for ICalEvent {
     fn serialize(&self) -> String {
         format!(r#"
             BEGIN:VEVENT
+            UID:{}
+            SEQUENCE:{}
             SUMMARY:{}
             DTSTART;TZID=Asia/Shanghai:{}
             DTEND;TZID=Asia/Shanghai:{}
             LOCATION:{}
             DESCRIPTION:{}
+            {}
+            {}
+            {}
+            {}
             END:VEVENT
         "#,
+            self.uid,
+            self.sequence,
             escape(&self.summary),
             format_utc8_str(&self.start),
             format_utc8_str(&self.end),
             escape(&self.location),
+            escape(&self.description),
+            self.color.as_ref().map(|c| format!("COLOR:{}", c)).unwrap_or("".to_string()),
+            if !self.categories.is_empty() {
+                format!("CATEGORIES:{}", self.categories.iter().map(|c| escape(c)).collect::<Vec<_>>().join(","))
+            } else {
+                "".to_string()
+            },
+            if self.cancelled { "STATUS:CANCELLED" } else { "" },
+            self.organizer.as_ref().map(|o| format!("ORGANIZER:{}", o)).unwrap_or("".to_string())
+        )
+    }
+}
+
+/*
+ * An event taking whole days, e.g. a holiday or an examination period
+ * It does not block the time in the calendar,
+ * since it is only there for information.
+ */
+pub struct ICalAllDayEvent {
+    pub uid: String,
+    pub summary: String,
+    pub start: Date<FixedOffset>,
+    pub end: Date<FixedOffset>, // Exclusive
+    pub description: String
+}
+
+impl ICalElement for ICalAllDayEvent {
+    fn serialize(&self) -> String {
+        format!(r#"
+            BEGIN:VEVENT
+            UID:{}
+            SUMMARY:{}
+            DTSTART;VALUE=DATE:{}
+            DTEND;VALUE=DATE:{}
+            DESCRIPTION:{}
+            TRANSP:TRANSPARENT
+            END:VEVENT
+        "#,
+            self.uid,
+            escape(&self.summary),
+            self.start.format("%Y%m%d"),
+            self.end.format("%Y%m%d"),
             escape(&self.description)
         )
     }
 }
 
 pub struct ICalBuilder {
-    elements: Vec<Box<ICalElement>>
+    method: Option<&'static str>,
+    elements: Vec<Box<dyn ICalElement>>
 }
 
 impl ICalBuilder {
-    fn new() -> ICalBuilder {
+    pub fn new() -> ICalBuilder {
+        ICalBuilder {
+            method: None,
+            elements: Vec::new()
+        }
+    }
+
+    /*
+     * A calendar with a `METHOD` (e.g. `REQUEST` or `CANCEL`)
+     * is treated as an update to existing events by calendar apps (RFC 5546)
+     */
+    pub fn with_method(method: &'static str) -> ICalBuilder {
         ICalBuilder {
+            method: Some(method),
             elements: Vec::new()
         }
     }
 
-    fn add<E: 'static + ICalElement>(&mut self, elem: E) {
+    pub fn add<E: 'static + ICalElement>(&mut self, elem: E) {
         self.elements.push(Box::new(elem));
     }
 }
 
+impl Default for ICalBuilder {
+    fn default() -> ICalBuilder {
+        ICalBuilder::new()
+    }
+}
+
 impl ICalElement for ICalBuilder {
     fn serialize(&self) -> String {
         let elem_str: Vec<_> = self.elements.iter()
@@ -116,19 +312,40 @@ impl ICalElement for ICalBuilder {
         trim_all_lines(format!(r#"
             BEGIN:VCALENDAR
             VERSION:2.0
+            PRODID:-//xjtlu-timetable//EN
             CALSCALE:GREGORIAN
             {}
+            {}
             END:VCALENDAR
-        "#, elem_str.concat()))
+        "#,
+            self.method.map(|m| format!("METHOD:{}", m)).unwrap_or("".to_string()),
+            elem_str.concat()
+        ))
     }
 }
 
+/*
+ * Generate a stable UID for an event
+ * The same class at the same time always gets the same UID,
+ * so that exporting the same timetable twice yields identical calendars.
+ * `details` are whatever tells apart events with the same summary and start
+ * (e.g. the location), since UIDs have to be unique within a calendar.
+ */
+pub fn event_uid(summary: &str, details: &[&str], start: &DateTime<FixedOffset>) -> String {
+    let key = details.iter().fold(summary.to_string(), |key, d| key + "\n" + d);
+    format!("{}-{:016x}@xjtlu-timetable", format_utc8_str(start), fnv1a(&key))
+}
+
+pub fn all_day_uid(summary: &str, start: &Date<FixedOffset>) -> String {
+    format!("{}-{:016x}@xjtlu-timetable", start.format("%Y%m%d"), fnv1a(summary))
+}
+
 fn escape(s: &str) -> String {
     s.replace(",", "\\,")
 }
 
 #[inline(always)]
-fn offset_utc8() -> FixedOffset {
+pub fn offset_utc8() -> FixedOffset {
     FixedOffset::east(8 * 3600)
 }
 
@@ -0,0 +1,195 @@
+/*
+ * Simple and naive ical file (.ics) reader
+ * Only understands what `ICalBuilder` writes, i.e. VEVENTs
+ * with UID, SEQUENCE, SUMMARY, DTSTART, DTEND, LOCATION,
+ * DESCRIPTION, COLOR, CATEGORIES, STATUS and ORGANIZER.
+ * Everything else, including all-day events, is ignored.
+ */
+use chrono::{DateTime, FixedOffset, NaiveDateTime, TimeZone, Utc};
+use ical::{offset_utc8, ICalEvent};
+
+/*
+ * Read all the events from an ical file
+ */
+pub fn parse_ical(s: &str) -> Result<Vec<ICalEvent>, String> {
+    let mut events = Vec::new();
+    // The event we are currently inside of, if any
+    let mut current: Option<PartialEvent> = None;
+
+    for (line_index, line) in unfold_lines(s).iter().enumerate() {
+        let (name, value) = split_property(line)
+            .ok_or(format!("Invalid line {} in calendar", line_index + 1))?;
+
+        match (name.as_str(), value.as_str()) {
+            ("BEGIN", "VEVENT") => {
+                current = Some(PartialEvent::default());
+            },
+            ("END", "VEVENT") => {
+                let event = current.take()
+                    .ok_or(format!("Unexpected END:VEVENT at line {}", line_index + 1))?;
+                // All-day events are not classes
+                if !event.all_day {
+                    events.push(event.build()
+                        .map_err(|e| format!("{} (line {})", e, line_index + 1))?);
+                }
+            },
+            _ => {
+                if let Some(ref mut event) = current {
+                    event.set(&name, &value)?;
+                }
+            }
+        }
+    }
+
+    if current.is_some() {
+        return Err("Unterminated VEVENT in calendar".to_string());
+    }
+    Ok(events)
+}
+
+#[derive(Default)]
+struct PartialEvent {
+    uid: Option<String>,
+    sequence: u32,
+    summary: Option<String>,
+    start: Option<DateTime<FixedOffset>>,
+    end: Option<DateTime<FixedOffset>>,
+    location: Option<String>,
+    description: Option<String>,
+    color: Option<String>,
+    categories: Vec<String>,
+    cancelled: bool,
+    organizer: Option<String>,
+    all_day: bool
+}
+
+impl PartialEvent {
+    fn set(&mut self, name: &str, value: &str) -> Result<(), String> {
+        // Parameters such as `TZID` are appended to the name after `;`
+        let mut parts = name.split(";");
+        let prop = parts.next().unwrap_or("");
+        let utc = value.ends_with("Z");
+
+        if (prop == "DTSTART" || prop == "DTEND") && parts.any(|p| p == "VALUE=DATE") {
+            self.all_day = true;
+            return Ok(());
+        }
+
+        match prop {
+            "UID" => self.uid = Some(value.to_string()),
+            "SEQUENCE" => self.sequence = value.parse()
+                .map_err(|_| format!("Invalid sequence {}", value))?,
+            "STATUS" => self.cancelled = value == "CANCELLED",
+            "ORGANIZER" => self.organizer = Some(value.to_string()),
+            "COLOR" => self.color = Some(value.to_string()),
+            "CATEGORIES" => self.categories.extend(split_list(value).iter().map(|c| unescape(c))),
+            "SUMMARY" => self.summary = Some(unescape(value)),
+            "LOCATION" => self.location = Some(unescape(value)),
+            "DESCRIPTION" => self.description = Some(unescape(value)),
+            "DTSTART" => self.start = Some(parse_datetime(value, utc)?),
+            "DTEND" => self.end = Some(parse_datetime(value, utc)?),
+            _ => ()
+        }
+        Ok(())
+    }
+
+    fn build(self) -> Result<ICalEvent, String> {
+        let summary = self.summary.unwrap_or("".to_string());
+        let start = self.start.ok_or("Event without DTSTART")?;
+        Ok(ICalEvent {
+            // Calendars exported by older versions do not have UIDs,
+            // such events are left with an empty one (see `diff::has_unknown_uids`)
+            uid: self.uid.unwrap_or_default(),
+            sequence: self.sequence,
+            summary,
+            start,
+            end: self.end.ok_or("Event without DTEND")?,
+            location: self.location.unwrap_or("".to_string()),
+            description: self.description.unwrap_or("".to_string()),
+            color: self.color,
+            categories: self.categories,
+            cancelled: self.cancelled,
+            organizer: self.organizer
+        })
+    }
+}
+
+/*
+ * Lines starting with a space or a tab are
+ * continuations of the previous line (RFC 5545 3.1)
+ */
+fn unfold_lines(s: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for line in s.lines() {
+        let line = line.trim_end_matches("\r");
+        if line.starts_with(" ") || line.starts_with("\t") {
+            if let Some(last) = lines.last_mut() {
+                last.push_str(&line[1..]);
+                continue;
+            }
+        }
+
+        if line.trim() != "" {
+            lines.push(line.trim().to_string());
+        }
+    }
+    lines
+}
+
+/*
+ * Split `NAME;PARAM=x:VALUE` into `NAME;PARAM=x` and `VALUE`
+ */
+fn split_property(line: &str) -> Option<(String, String)> {
+    line.find(":")
+        .map(|i| (line[..i].to_uppercase(), line[(i + 1)..].to_string()))
+}
+
+/*
+ * Times without the `Z` suffix are considered to be in GMT+8
+ * since that is the only timezone `ICalBuilder` writes.
+ */
+fn parse_datetime(value: &str, utc: bool) -> Result<DateTime<FixedOffset>, String> {
+    let naive = NaiveDateTime::parse_from_str(value.trim_end_matches("Z"), "%Y%m%dT%H%M%S")
+        .map_err(|_| format!("Invalid time {}", value))?;
+    if utc {
+        Ok(Utc.from_utc_datetime(&naive).with_timezone(&offset_utc8()))
+    } else {
+        offset_utc8().from_local_datetime(&naive).single()
+            .ok_or(format!("Invalid time {}", value))
+    }
+}
+
+/*
+ * Split a list value by the commas that are not escaped
+ */
+fn split_list(s: &str) -> Vec<String> {
+    let mut ret = vec![String::new()];
+    let mut escaped = false;
+    for c in s.chars() {
+        if c == ',' && !escaped {
+            ret.push(String::new());
+        } else {
+            ret.last_mut().unwrap().push(c);
+        }
+        escaped = c == '\\' && !escaped;
+    }
+    ret
+}
+
+fn unescape(s: &str) -> String {
+    let mut ret = String::new();
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            ret.push(c);
+            continue;
+        }
+
+        match chars.next() {
+            Some('n') | Some('N') => ret.push('\n'),
+            Some(c) => ret.push(c),
+            None => ret.push('\\')
+        }
+    }
+    ret
+}
@@ -0,0 +1,51 @@
+/*
+ * Import / export of parsed timetables as JSON.
+ * The JSON format is simply the serialized form of `Vec<Class>`,
+ * so that users can keep a copy of their timetable, tweak it by hand
+ * and regenerate the calendar later without E-Bridge.
+ */
+use class::Class;
+use serde_json;
+
+/*
+ * Serialize a list of classes into (pretty-printed) JSON
+ */
+pub fn classes_to_json(classes: &[Class]) -> String {
+    // Serializing plain structs into a String never fails
+    serde_json::to_string_pretty(classes).unwrap()
+}
+
+/*
+ * Load a list of classes from a previously exported JSON string
+ * Since the file may have been edited by hand, the values are
+ * checked before being handed to the calendar exporter.
+ */
+pub fn classes_from_json(s: &str) -> Result<Vec<Class>, String> {
+    let classes: Vec<Class> = serde_json::from_str(s)
+        .map_err(|e| format!("Invalid JSON timetable: {}", e))?;
+
+    for (i, class) in classes.iter().enumerate() {
+        if class.day > 6 {
+            return Err(format!("Invalid day of class {}", i));
+        }
+
+        if class.start.hour > 23 {
+            return Err(format!("Invalid start time of class {}", i));
+        }
+
+        // Classes end by midnight
+        if class.len == 0 || class.len > 48 - class.start.half_hours() {
+            return Err(format!("Invalid length of class {}", i));
+        }
+    }
+
+    Ok(classes)
+}
+
+/*
+ * Check whether a piece of user input looks like a JSON timetable
+ * rather than an E-Bridge page.
+ */
+pub fn is_json(s: &str) -> bool {
+    s.trim_start().starts_with("[")
+}
@@ -0,0 +1,33 @@
+/*
+ * The browser-independent part of the timetable exporter.
+ * Shared by the web frontend (`main.rs`) and the command line tool.
+ */
+extern crate chrono;
+extern crate serde;
+#[macro_use]
+extern crate serde_derive;
+extern crate serde_json;
+extern crate base64;
+#[cfg(feature = "caldav")]
+extern crate ureq;
+
+pub mod constants;
+pub mod semester;
+pub mod class;
+pub mod occurrence;
+pub mod query;
+pub mod free_slots;
+pub mod ical;
+pub mod ical_reader;
+pub mod diff;
+pub mod json;
+pub mod html;
+pub mod mhtml;
+pub mod table_grid;
+pub mod parser;
+pub mod text_parser;
+pub mod color;
+pub mod render;
+#[cfg(feature = "caldav")]
+pub mod caldav;
+mod hash;
@@ -1,24 +1,49 @@
+/*
+ * The web frontend, only built for wasm32 (see `deploy.sh`)
+ */
+#[cfg(target_arch = "wasm32")]
 extern crate base64;
-extern crate chrono;
-#[macro_use]
-extern crate stdweb;
+#[cfg(target_arch = "wasm32")]
+extern crate wasm_bindgen;
+#[cfg(target_arch = "wasm32")]
+extern crate web_sys;
+#[cfg(target_arch = "wasm32")]
+extern crate xjtlu_timetable;
 
-mod constants;
+#[cfg(target_arch = "wasm32")]
 #[macro_use]
 mod util;
-mod ical;
-mod parser;
+#[cfg(target_arch = "wasm32")]
 mod app;
+#[cfg(target_arch = "wasm32")]
+mod dialog;
+
+#[cfg(target_arch = "wasm32")]
+use util::document;
 
-use stdweb::web::{document, IParentNode};
+#[cfg(not(target_arch = "wasm32"))]
+fn main() {
+    eprintln!("The web frontend only runs in browsers, see deploy.sh. Use xjtlu-timetable-cli instead.");
+    std::process::exit(1);
+}
 
+#[cfg(target_arch = "wasm32")]
 fn main() {
     let elem_input = document().query_selector("#paste-area").unwrap().unwrap();
+    let elem_file = document().query_selector("#timetable-file").unwrap().unwrap();
+    let elem_previous = document().query_selector("#previous-ics").unwrap().unwrap();
+    let elem_semester = document().query_selector("#semester").unwrap().unwrap();
+    let elem_colors = document().query_selector("#module-colors").unwrap().unwrap();
+    let elem_holidays = document().query_selector("#export-holidays").unwrap().unwrap();
+    let elem_milestones = document().query_selector("#export-milestones").unwrap().unwrap();
+    let elem_week_numbers = document().query_selector("#export-week-numbers").unwrap().unwrap();
     let elem_submit = document().query_selector("#submit").unwrap().unwrap();
     let elem_help = document().query_selector("#help").unwrap().unwrap();
     let dialog_help = document().query_selector("#dialog-help").unwrap().unwrap();
     let dialog_download = document().query_selector("#dialog-download").unwrap().unwrap();
     let dialog_info = document().query_selector("#dialog-info").unwrap().unwrap();
-    let app = app::App::new(elem_input, elem_submit, elem_help, dialog_help, dialog_download, dialog_info);
+    let app = app::App::new(elem_input, elem_file, elem_previous, elem_semester, elem_colors,
+        elem_holidays, elem_milestones, elem_week_numbers, elem_submit, elem_help,
+        dialog_help, dialog_download, dialog_info);
     app.run();
 }
@@ -0,0 +1,137 @@
+/*
+ * Read pages saved as MHTML ("Webpage, Single File"),
+ * the default of Chrome on Android and of some desktop browsers.
+ * Such a file is a MIME message (RFC 2557) whose first
+ * `text/html` part is the page itself.
+ */
+use base64;
+
+// Whether `content` looks like a MIME message rather than HTML
+pub fn is_mhtml(content: &str) -> bool {
+    let (headers, _) = split_headers(content.trim_start());
+    header_value(&headers, "content-type")
+        .is_some_and(|t| t.to_ascii_lowercase().starts_with("multipart/related"))
+}
+
+/*
+ * The HTML page of an MHTML file, decoded
+ * Only UTF-8 and ASCII pages are supported, other
+ * charsets are read as UTF-8 with replacement characters.
+ */
+pub fn extract_html(content: &str) -> Result<String, String> {
+    let (headers, body) = split_headers(content.trim_start());
+    let content_type = header_value(&headers, "content-type")
+        .ok_or("Not an MHTML file: no Content-Type".to_string())?;
+    let boundary = header_param(&content_type, "boundary")
+        .ok_or("Invalid MHTML file: no boundary".to_string())?;
+
+    let delimiter = format!("--{}", boundary);
+    for part in body.split(delimiter.as_str()).skip(1) {
+        if part.starts_with("--") {
+            // The closing delimiter
+            break;
+        }
+        let (headers, body) = split_headers(part.trim_start_matches(['\r', '\n']));
+        let is_html = header_value(&headers, "content-type")
+            .is_some_and(|t| t.to_ascii_lowercase().starts_with("text/html"));
+        if !is_html {
+            continue;
+        }
+
+        let encoding = header_value(&headers, "content-transfer-encoding")
+            .unwrap_or("7bit".to_string())
+            .to_ascii_lowercase();
+        let bytes = match encoding.trim() {
+            "quoted-printable" => decode_quoted_printable(body),
+            "base64" => {
+                let data: String = body.chars().filter(|c| !c.is_whitespace()).collect();
+                base64::decode(&data).map_err(|e| format!("Invalid MHTML file: {}", e))?
+            },
+            _ => body.as_bytes().to_vec()
+        };
+        return Ok(String::from_utf8_lossy(&bytes).into_owned());
+    }
+    Err("No HTML page found in the MHTML file".to_string())
+}
+
+/*
+ * Decode quoted-printable text (RFC 2045)
+ * `=XX` is the byte XX and `=` at the end of a line joins it with the next one.
+ */
+pub fn decode_quoted_printable(s: &str) -> Vec<u8> {
+    let bytes = s.as_bytes();
+    let hex = |i: usize| bytes.get(i).and_then(|b| (*b as char).to_digit(16));
+    let mut ret = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] != b'=' {
+            ret.push(bytes[i]);
+            i += 1;
+            continue;
+        }
+
+        match (hex(i + 1), hex(i + 2)) {
+            (Some(high), Some(low)) => {
+                ret.push((high * 16 + low) as u8);
+                i += 3;
+            },
+            _ => {
+                // A soft line break, possibly after trailing whitespace
+                let mut j = i + 1;
+                while j < bytes.len() && (bytes[j] == b' ' || bytes[j] == b'\t') {
+                    j += 1;
+                }
+                if bytes[j..].starts_with(b"\r\n") {
+                    i = j + 2;
+                } else if bytes[j..].starts_with(b"\n") {
+                    i = j + 1;
+                } else {
+                    // Not valid, keep it as it is
+                    ret.push(b'=');
+                    i += 1;
+                }
+            }
+        }
+    }
+    ret
+}
+
+// The headers, unfolded into one line each, and the body after them
+fn split_headers(s: &str) -> (Vec<String>, &str) {
+    let mut headers: Vec<String> = Vec::new();
+    let mut rest = s;
+    loop {
+        let end = rest.find('\n').unwrap_or(rest.len());
+        let line = rest[..end].trim_end_matches('\r');
+        rest = if end < rest.len() { &rest[(end + 1)..] } else { "" };
+
+        if line.is_empty() {
+            return (headers, rest);
+        }
+        match headers.last_mut() {
+            Some(last) if line.starts_with([' ', '\t']) => {
+                last.push(' ');
+                last.push_str(line.trim());
+            },
+            _ => headers.push(line.to_string())
+        }
+        if rest.is_empty() {
+            return (headers, rest);
+        }
+    }
+}
+
+fn header_value(headers: &[String], name: &str) -> Option<String> {
+    headers.iter()
+        .filter_map(|h| h.find(':').map(|i| (&h[..i], &h[(i + 1)..])))
+        .find(|(n, _)| n.trim().eq_ignore_ascii_case(name))
+        .map(|(_, v)| v.trim().to_string())
+}
+
+// A parameter of a header value, e.g. `boundary` in `multipart/related; boundary="..."`
+fn header_param(value: &str, name: &str) -> Option<String> {
+    value.split(';').skip(1)
+        .filter_map(|p| p.find('=').map(|i| (&p[..i], &p[(i + 1)..])))
+        .find(|(n, _)| n.trim().eq_ignore_ascii_case(name))
+        .map(|(_, v)| v.trim().trim_matches('"').to_string())
+}
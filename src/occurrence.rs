@@ -0,0 +1,71 @@
+/*
+ * Expand the weekly classes of a timetable into the concrete
+ * sessions taking place during a semester
+ */
+use chrono::{Date, DateTime, Duration, FixedOffset};
+use class::Class;
+use semester::{ClassDay, Semester};
+use std::vec;
+
+/*
+ * A single session of a class
+ */
+#[derive(Clone, Debug)]
+pub struct Occurrence<'a> {
+    pub class: &'a Class,
+    pub week: usize, // Starting from 1
+    pub date: Date<FixedOffset>,
+    pub start: DateTime<FixedOffset>,
+    pub end: DateTime<FixedOffset>
+}
+
+/*
+ * Iterator over the sessions of a list of classes, in order of their dates.
+ * Sessions on the same day come in the order of the classes.
+ */
+pub struct Occurrences<'a> {
+    classes: &'a [Class],
+    days: vec::IntoIter<ClassDay>,
+    day: Option<ClassDay>,
+    index: usize // Index of the next class to check on `day`
+}
+
+pub fn occurrences<'a>(classes: &'a [Class], semester: &Semester) -> Occurrences<'a> {
+    let mut days = semester.class_days().into_iter();
+    let day = days.next();
+    Occurrences { classes, days, day, index: 0 }
+}
+
+impl<'a> Iterator for Occurrences<'a> {
+    type Item = Occurrence<'a>;
+
+    fn next(&mut self) -> Option<Occurrence<'a>> {
+        loop {
+            let day = match self.day {
+                Some(ref day) => day.clone(),
+                None => return None
+            };
+
+            while self.index < self.classes.len() {
+                let class = &self.classes[self.index];
+                self.index += 1;
+                if class.day != day.weekday || !class.has_week(day.week) {
+                    continue;
+                }
+
+                let min = if class.start.half { 30 } else { 0 };
+                let start = day.date.and_hms(class.start.hour as u32, min, 0);
+                return Some(Occurrence {
+                    class,
+                    week: day.week,
+                    date: day.date,
+                    start,
+                    end: start + Duration::minutes((class.len as i64) * 30)
+                });
+            }
+
+            self.day = self.days.next();
+            self.index = 0;
+        }
+    }
+}
@@ -1,44 +1,72 @@
-use stdweb::unstable::TryInto;
-use stdweb::web::{IElement, INode, IParentNode, Element, NodeList};
-use util::{ToElement, ElementAttribute};
-
-#[derive(Clone, Debug)]
-pub struct ClassTime {
-    pub hour: usize,
-    pub half: bool
-}
-
-#[derive(Debug)]
-pub struct Class {
-    pub title: String,
-    pub lecturer: String,
-    pub location: String,
-    pub day: usize, // Day in a week
-    pub weeks: [bool; 14], // Specify whether a class is available on week x
-    pub start: ClassTime, 
-    pub len: usize // length in half-hours
-}
+use class::{Class, ClassTime};
+use html::{parse_html, Element};
+use mhtml;
+use table_grid::TableGrid;
+use text_parser;
 
 /*
- * Try to parse the content of an element as XJTLU class timetable
+ * Try to parse an HTML page (or a part of it) as XJTLU class timetable
  * If it is not, an Err will be returned
  */
-pub fn parse(elem: &Element) -> Result<Vec<Class>, String> {
+pub fn parse(html: &str) -> Result<Vec<Class>, String> {
+    let document = parse_html(html);
     // For XJTLU class timetables, all the content
     // Are inside a table whose class is `.maintable`
     // First find all the rows of that table
-    elem.query_selector_all(".maintable > tbody > tr")
-        .map_err(|_| "Failed to find the timetable element".to_string())
-        .and_then(|rows| {
-            // Parse the row list
-            parse_rows(rows)
-        })
+    let table = document.select("table", Some("maintable")).into_iter().next()
+        .ok_or("Failed to find the timetable element".to_string())?;
+    parse_rows(table_rows(table))
 }
 
-#[derive(Clone)]
-struct RowSpanCell {
-    cell_index: usize,
-    remaining_rows: usize
+/*
+ * Parse the timetable page saved by a browser,
+ * either as plain HTML or as MHTML
+ */
+pub fn parse_saved_page(content: &str) -> Result<Vec<Class>, String> {
+    parse(&saved_page_html(content)?)
+}
+
+/*
+ * Like `parse_saved_page`, but if there is no timetable in the page,
+ * e.g. because the browser pasted plain text, try to read the classes
+ * from its text (see `text_parser`).
+ * Returns warnings about anything that may have been read wrong.
+ */
+pub fn parse_best_effort(content: &str) -> Result<(Vec<Class>, Vec<String>), String> {
+    let html = saved_page_html(content)?;
+    let document = parse_html(&html);
+    if !document.select("table", Some("maintable")).is_empty() {
+        return parse(&html).map(|classes| (classes, Vec::new()));
+    }
+
+    let (classes, mut warnings) = text_parser::parse_text(&document.inner_text())
+        .map_err(|err| format!("Failed to find the timetable element, nor a timetable in the text: {}", err))?;
+    if classes.is_empty() {
+        return Err("Failed to find the timetable element, nor any class in the text".to_string());
+    }
+    warnings.insert(0, "The timetable was read from plain text, which is best-effort: please check the classes".to_string());
+    Ok((classes, warnings))
+}
+
+fn saved_page_html(content: &str) -> Result<String, String> {
+    if mhtml::is_mhtml(content) {
+        mhtml::extract_html(content)
+    } else {
+        Ok(content.to_string())
+    }
+}
+
+// The rows of a table, whether or not they are inside a `tbody`
+fn table_rows(table: &Element) -> Vec<&Element> {
+    let mut rows = Vec::new();
+    for child in table.children() {
+        match child.name.as_str() {
+            "tr" => rows.push(child),
+            "thead" | "tbody" | "tfoot" => rows.extend(child.children().into_iter().filter(|e| e.name == "tr")),
+            _ => ()
+        }
+    }
+    rows
 }
 
 /*
@@ -46,160 +74,101 @@ struct RowSpanCell {
  * of XJTLU class timetable.
  * All the information should be in this list.
  */
-fn parse_rows(rows: NodeList) -> Result<Vec<Class>, String> {
-    if rows.len() == 0 {
+fn parse_rows(rows: Vec<&Element>) -> Result<Vec<Class>, String> {
+    if rows.is_empty() {
         return Err("Invalid timetable".to_string());
     }
-    
-    let mut ret: Vec<Class> = Vec::new();
-    // Actually a Map from column to its corresponding weekday
-    let mut col_to_weekday: Vec<usize> = Vec::new();
-    let mut row_span_cells: Vec<RowSpanCell> = Vec::new();
-    let mut pending_span_cells: Vec<RowSpanCell> = Vec::new();
 
-    for (row_index, r) in rows.iter().enumerate() {
-        // A row must be an Element
-        let row_elem = r.to_element()
-            .ok_or(format!("Invalid row {}", row_index))?;
+    // All the cells representing classes in XJTLU class timetable
+    // span over multiple rows, using the `rowspan` attribute, and
+    // the headers of weekdays split into several columns use `colspan`.
+    // Thus the n-th cell of a row is not necessarily in the n-th column,
+    // which the grid takes care of.
+    let grid = TableGrid::new(&rows)?;
+
+    let mut ret: Vec<Class> = Vec::new();
+    // The weekday of each column of the grid, if it is one of the weekdays
+    let mut col_to_weekday: Vec<Option<usize>> = vec![None; grid.width()];
 
-        if row_elem.class_list().contains("rowtitle") {
+    for (row_index, row_elem) in rows.into_iter().enumerate() {
+        if row_elem.has_class("rowtitle") {
             // The title row, for correspondence between column and weekdays
             // In some class timetables, one weekday may be split into
             // multiple columns to have multiple classes for different
-            // weeks in the same time slot.
-            // By reading the `colspan` value of the headers, we can
-            // build a table of correspondence between column and its
-            // weekday index.
-            let weekdays = row_elem.query_selector_all("td")
-                .map_err(|_| "No proper header found".to_string())?
-                .iter()
-                .skip(1); // The first one is always empty
-            let mut current_weekday = 0;
-            for d in weekdays {
-                if current_weekday > 6 {
-                    return Err("Invalid header row: More than 7 days".to_string());
-                }
-
-                let colspan_value: usize = d.to_element()
-                    .ok_or("Invalid header".to_string())?
-                    .get_attribute("colspan").try_into()
-                    .map_err(|_| ())
-                    .and_then(|s: String| s.parse()
-                        .map_err(|_| ()))
-                    .unwrap_or(1); 
-
-                for _ in 0..colspan_value {
-                    // We use an array to build the correspondence
-                    // It is actually used like a Map
-                    // The indexes
-                    // (col_to_weekday.len() - 1) to (col_to_weekday.len() - 1 + colspan_value)
-                    // all correspond to `current_weekday`
-                    col_to_weekday.push(current_weekday);
+            // weeks in the same time slot, with a `colspan` header.
+            // The weekdays are told by the text of the headers, since
+            // timetables may start on Sunday or leave out the weekend.
+            for (i, cell) in grid.row_cells(row_index).into_iter().enumerate() {
+                let text = cell.elem.text_content().replace('\u{a0}', " ");
+                let weekday = match weekday_from_header(&text) {
+                    Some(weekday) => weekday,
+                    // The first one is the empty corner above the times
+                    None if i == 0 => continue,
+                    None => return Err(format!("Invalid header row: unknown weekday {}", text.trim()))
+                };
+                for day in &mut col_to_weekday[cell.col..(cell.col + cell.colspan)] {
+                    *day = Some(weekday);
                 }
-                current_weekday += 1;
             }
 
             // The title row will contain nothing but the weekdays
             continue;
         }
 
-        if col_to_weekday.len() < 7 {
-            return Err("Invalid header row: less than 7 days found".to_string());
+        if col_to_weekday.iter().all(|d| d.is_none()) {
+            return Err("Invalid header row: no weekdays found".to_string());
         }
 
-        // A row must have a cell whose class is `coltitle` which
+        // A row must be covered by a cell whose class is `coltitle` which
         // indicates the corresponding class time of this row.
-        let coltitle = row_elem.query_selector("td.coltitle")
-            .map_err(|_| "")
-            .and_then(|r| r.ok_or(""))
-            .and_then(|elem| elem.text_content().ok_or(""))
-            .unwrap_or("".to_string());
-        if coltitle == "" {
+        // If that cell spans several rows, each row is another half hour.
+        let coltitle = (0..grid.width())
+            .filter_map(|col| grid.cell_at(row_index, col))
+            .find(|c| c.elem.has_class("coltitle"));
+        let (coltitle, rows_below) = match coltitle {
+            Some(c) => (c.elem.text_content(), row_index - c.row),
+            None => continue
+        };
+        if coltitle.trim() == "" {
             continue;
         }
         let time_split: Vec<_> = coltitle.trim().split(":").collect();
         if time_split.len() != 2 {
             return Err(format!("Invalid time at row {}", row_index));
         }
-        let current_start_time = ClassTime {
-            hour: time_split[0].parse().map_err(|_| format!("Invalid hour at row {}", row_index))?,
-            half: time_split[1] == "30"
-        };
-
-        // Find all the grid cells
-        let row_columns = row_elem.query_selector_all("td.gridcell")
-                .map_err(|_| format!("Invalid row {}", row_index))?;
+        let hour: usize = time_split[0].trim().parse().map_err(|_| format!("Invalid hour at row {}", row_index))?;
+        if hour > 23 {
+            return Err(format!("Invalid hour at row {}", row_index));
+        }
+        let label_time = ClassTime { hour, half: time_split[1].trim() == "30" };
+        let current_start_time = ClassTime::from_half_hours(label_time.half_hours() + rows_below);
 
-        // All the cells representing classes in XJTLU class timetable
-        // will span over multiple rows, using the `rowspan` attribute.
-        // Thus row may contain implicit hidden cells (i.e. not in `td` list)
-        // that is a cell from a former row.
-        // For example, if the former row has a cell at column 0
-        // whose `rowspan` is 2, then the first cell of the current row
-        // is actually the cell at colum 1 instead of column 0.
-        // So we need an `offset` state to keep track of this for each row.
-        let mut offset = 0;
-        for (j, c) in row_columns.iter().enumerate() {
-            let mut col_index = j + offset;
-
-            // Loop over all the previously known cells that have a `rowspan` attribute
-            for span_cell in &row_span_cells {
-                // Increase offset by 1 if there is a cell that have a `rowspan`
-                // previously at the current column.
-                if span_cell.cell_index == col_index {
-                    col_index += 1;
-                    offset += 1;
-                }
-            }
+        // If the cell is a `nonemptycell` then it represents a class
+        // and have a `rowspan` attribute that corresponds to the half-hours
+        // a class has.
+        for cell in grid.row_cells(row_index).into_iter().filter(|c| c.elem.has_class("nonemptycell")) {
+            let col_index = cell.col;
 
             // Boundary check
-            if col_index > col_to_weekday.len() {
+            if col_index >= col_to_weekday.len() {
                 return Err("More columns found than weekdays".to_string());
             }
+            // A class spanning several columns is on the weekday of the first one
+            let weekday = col_to_weekday[col_index]
+                .ok_or(format!("Class outside of the weekdays at {}:{}", row_index, col_index))?;
 
-            // A column must always be an element too.
-            let column_elem = c.to_element()
-                .ok_or(format!("Invalid column {}:{}", row_index, col_index))?;
-
-            // If the cell is a `nonemptycell` then it represents a class
-            // and have a `rowspan` attribute that corresponds to the half-hours
-            // a class has.
-            if column_elem.class_list().contains("nonemptycell") {
-                let rowspan_value: usize = column_elem.get_attribute("rowspan").try_into()
-                    .map_err(|_| ())
-                    .and_then(|s: String| s.parse()
-                        .map_err(|_| ()))
-                    .unwrap_or(0);
-                if rowspan_value > 0 {
-                    // Record the rowspan attribute of this cell
-                    // but it should be pending until we finish
-                    // this row.
-                    pending_span_cells.push(RowSpanCell {
-                        cell_index: col_index,
-                        remaining_rows: rowspan_value
-                    })
-                } else {
-                    // As far as I am concerned, there is no half-an-hour classes.
-                    return Err(format!("Invalid class at {}:{}", row_index, col_index));
-                }
-
-                // Parse the class information
-                /*let text = column_elem.text_content()
-                    .ok_or(format!("Class empty at {}:{}", row_index, col_index))?;*/
-                ret.push(parse_class_content(row_index, col_index, col_to_weekday[col_index], current_start_time.clone(), rowspan_value, &column_elem)?);
+            if cell.elem.attr_usize("rowspan").unwrap_or(0) == 0 {
+                // As far as I am concerned, there is no half-an-hour classes.
+                return Err(format!("Invalid class at {}:{}", row_index, col_index));
+            }
+            // Classes end by midnight
+            if current_start_time.half_hours() + cell.rowspan > 48 {
+                return Err(format!("Invalid class at {}:{}", row_index, col_index));
             }
-        }
 
-        // Append all pending cells into the span cells list
-        row_span_cells.append(&mut pending_span_cells);
-        
-        // Decrease all `remaining_rows` by 1
-        for span_cell in &mut row_span_cells {
-            span_cell.remaining_rows -= 1;
+            // Parse the class information
+            ret.push(parse_class_content(row_index, col_index, weekday, current_start_time.clone(), cell.rowspan, cell.elem)?);
         }
-
-        // Remove the span cells that do not have any remining rows
-        row_span_cells.retain(|cell| cell.remaining_rows != 0);
     }
     Ok(ret)
 }
@@ -211,41 +180,89 @@ fn parse_rows(rows: NodeList) -> Result<Vec<Class>, String> {
  * >  lecturers
  * >  location
  * >  Week: x-y, z-w, t, ...
+ * The last line may also be in Chinese (see `parse_week_line`).
  */
 fn parse_class_content(row_index: usize, col_index: usize, day: usize, class_start: ClassTime, len: usize, content: &Element) -> Result<Class, String> {
     // Get all the lines from the current cell
     // We can't use text_content() and just split()
-    // because different browsers have different logic on
-    // how to add line breaks
-    let lines = content.query_selector_all("tr.inR")
-        .map_err(|_| format!("Invalid cell at {}:{}", row_index, col_index))?
-        .iter()
-        .map(|l| l.text_content().ok_or(format!("Invalid cell at {}:{}", row_index, col_index)))
-        .fold(Ok(Vec::new()), |x, y| {
-            if let Ok(mut v) = x {
-                if let Ok(line) = y {
-                    v.push(line.replace("\n", "").trim().to_string());
-                    return Ok(v);
-                }
-            }
-            Err(format!("Invalid cell at {}:{}", row_index, col_index))
-        })?;
+    // because the line breaks depend on how the page was saved
+    let lines: Vec<String> = content.select("tr", Some("inR"))
+        .into_iter()
+        .map(|l| l.text_content().replace("\n", "").replace('\u{a0}', " ").trim().to_string())
+        .collect();
+    if lines.is_empty() {
+        return Err(format!("Invalid cell at {}:{}", row_index, col_index));
+    }
 
-    if lines.len() != 4 || !lines[3].starts_with("Week:") {
+    if lines.len() != 4 || !is_week_line(&lines[3]) {
         return Err("Information corrupted".to_string());
     }
 
+    let weeks = parse_week_line(&lines[3])?;
+
+    Ok(Class {
+        title: lines[0].to_string(),
+        lecturer: lines[1].to_string(),
+        location: lines[2].to_string(),
+        day,
+        weeks,
+        start: class_start,
+        len,
+        owner: None
+    })
+}
+
+/*
+ * Whether a line of a class cell is the list of its weeks,
+ * `Week: 1-7, 9` in English and `周: 1-7, 9` or `第1-7, 9周` in Chinese
+ */
+pub fn is_week_line(line: &str) -> bool {
+    let line = line.trim();
+    week_line_prefix(line).is_some() || (line.starts_with('第') && line.ends_with('周'))
+}
+
+/*
+ * Parse the list of weeks of a class, see `is_week_line`
+ * Full-width punctuation of the Chinese layout is accepted as well.
+ */
+pub fn parse_week_line(line: &str) -> Result<[bool; 14], String> {
+    let line = line.trim();
+    let list = match week_line_prefix(line) {
+        Some(prefix) => &line[prefix.len()..],
+        None => line
+    };
+    let list: String = list.chars()
+        .filter(|&c| c != '第' && c != '周')
+        .map(|c| match c {
+            '，' | '、' => ',',
+            '－' | '–' | '—' | '~' | '～' | '至' => '-',
+            c => c
+        })
+        .collect();
+    parse_weeks(&list)
+}
+
+fn week_line_prefix(line: &str) -> Option<&'static str> {
+    ["Week:", "Week：", "周:", "周："].iter()
+        .find(|p| line.starts_with(*p))
+        .cloned()
+}
+
+/*
+ * Parse the weeks a class takes place in, e.g. `1-7, 9`
+ */
+pub fn parse_weeks(text: &str) -> Result<[bool; 14], String> {
     // If the class is available on week x, we later set week[x - 1] = true
     let mut weeks = [false; 14];
 
-    // Parse the week range string
-    let _weeks_text = lines[3].replace("Week:", "");
-    let weeks_text = _weeks_text.trim().split(",");
-    for week_range in weeks_text {
+    for week_range in text.trim().split(",") {
         let start_end: Vec<_> = week_range.trim().split("-").collect();
         if start_end.len() == 1 {
             // Just one week
             let w: usize = start_end[0].parse().map_err(|_| "Invalid week string".to_string())?;
+            if w == 0 || w > weeks.len() {
+                return Err("Invalid week string".to_string());
+            }
             weeks[w - 1] = true;
             continue;
         }
@@ -254,21 +271,36 @@ fn parse_class_content(row_index: usize, col_index: usize, day: usize, class_sta
         }
         let start: usize = start_end[0].parse().map_err(|_| "Invalid week string".to_string())?;
         let end: usize = start_end[1].parse().map_err(|_| "Invalid week string".to_string())?;
-        if !(start > 0 && end > 0 && end > start) {
+        if !(start > 0 && end > 0 && end > start && end <= weeks.len()) {
             return Err("Information corrupted".to_string());
         }
-        for i in (start - 1)..end {
-            weeks[i] = true;
+        for week in &mut weeks[(start - 1)..end] {
+            *week = true;
         }
     }
+    Ok(weeks)
+}
 
-    Ok(Class {
-        title: lines[0].to_string(),
-        lecturer: lines[1].to_string(),
-        location: lines[2].to_string(),
-        day,
-        weeks,
-        start: class_start,
-        len
-    })
-}
\ No newline at end of file
+/*
+ * The weekday named by a header of the timetable,
+ * e.g. `Monday` or `Mon`, and `星期一` or `周一` in Chinese
+ * Returns 0 for Monday, same as `Class::day`
+ */
+pub fn weekday_from_header(text: &str) -> Option<usize> {
+    const WEEKDAYS: &[&str] = &[
+        "monday", "tuesday", "wednesday", "thursday", "friday", "saturday", "sunday"
+    ];
+    const CHINESE_WEEKDAYS: &[&str] = &["一", "二", "三", "四", "五", "六", "日"];
+
+    let text = text.trim();
+    if let Some(day) = ["星期", "周", "礼拜"].iter().filter_map(|p| text.strip_prefix(*p)).next() {
+        let day = if day == "天" { "日" } else { day };
+        return CHINESE_WEEKDAYS.iter().position(|d| *d == day);
+    }
+
+    let text = text.trim_end_matches('.').to_lowercase();
+    if text.len() < 3 {
+        return None;
+    }
+    WEEKDAYS.iter().position(|d| d.starts_with(&text))
+}
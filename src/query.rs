@@ -0,0 +1,95 @@
+/*
+ * Questions about a timetable, such as
+ * "what do I have today" or "what is my next class"
+ */
+use chrono::{Date, DateTime, Datelike, FixedOffset, Utc};
+use class::Class;
+use ical::offset_utc8;
+use occurrence::{occurrences, Occurrence};
+use semester::Semester;
+
+const WEEKDAYS: &[&str] = &[
+    "Monday", "Tuesday", "Wednesday", "Thursday", "Friday", "Saturday", "Sunday"
+];
+
+// The current time in Suzhou
+pub fn now() -> DateTime<FixedOffset> {
+    Utc::now().with_timezone(&offset_utc8())
+}
+
+/*
+ * The sessions taking place on `date`, in order of their start
+ */
+pub fn sessions_on<'a>(classes: &'a [Class], semester: &Semester, date: &Date<FixedOffset>) -> Vec<Occurrence<'a>> {
+    let mut sessions: Vec<_> = occurrences(classes, semester)
+        .skip_while(|o| o.date < *date)
+        .take_while(|o| o.date == *date)
+        .collect();
+    sessions.sort_by_key(|o| o.start);
+    sessions
+}
+
+/*
+ * The session going on at `time`, or the first one after it
+ */
+pub fn next_session<'a>(classes: &'a [Class], semester: &Semester, time: &DateTime<FixedOffset>) -> Option<Occurrence<'a>> {
+    // Sessions of the same day come in the order of the classes, not of their times
+    occurrences(classes, semester)
+        .filter(|o| o.end > *time)
+        .min_by_key(|o| o.start)
+}
+
+/*
+ * The sessions of a teaching week, optionally only those of one weekday,
+ * in order of their start
+ * Note that the weekday is that of the timetable, so on make-up days
+ * the sessions may take place on another day of the week.
+ */
+pub fn sessions_in_week<'a>(classes: &'a [Class], semester: &Semester, week: usize, weekday: Option<usize>) -> Vec<Occurrence<'a>> {
+    let mut sessions: Vec<_> = occurrences(classes, semester)
+        .filter(|o| o.week == week)
+        .filter(|o| weekday.is_none_or(|d| o.class.day == d))
+        .collect();
+    sessions.sort_by_key(|o| o.start);
+    sessions
+}
+
+/*
+ * Parse the name of a weekday, e.g. `Tuesday`, `tue` or `2`
+ * Returns 0 for Monday, same as `Class::day`
+ */
+pub fn parse_weekday(s: &str) -> Option<usize> {
+    let s = s.trim().to_lowercase();
+    if let Ok(n) = s.parse::<usize>() {
+        return if (1..=7).contains(&n) { Some(n - 1) } else { None };
+    }
+    if s.len() < 2 {
+        return None;
+    }
+    WEEKDAYS.iter().position(|d| d.to_lowercase().starts_with(&s))
+}
+
+/*
+ * Human-readable list of sessions, one per line
+ */
+pub fn format_sessions(sessions: &[Occurrence]) -> String {
+    if sessions.is_empty() {
+        return "No classes.\n".to_string();
+    }
+
+    let mut ret = String::new();
+    for o in sessions {
+        ret.push_str(&format!(
+            "{} {} (Week {})  {}-{}  {}  {}  {}\n",
+            &WEEKDAYS[o.date.weekday().num_days_from_monday() as usize][..3],
+            o.date.format("%Y-%m-%d"),
+            o.week,
+            o.start.format("%H:%M"),
+            o.end.format("%H:%M"),
+            o.class.title,
+            o.class.location,
+            o.class.lecturer
+        ));
+    }
+    ret
+}
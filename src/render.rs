@@ -0,0 +1,286 @@
+/*
+ * Render a timetable as a printable weekly grid,
+ * either as SVG or as a (very simple) PDF file.
+ * Both are drawn from the same layout, which consists of
+ * nothing but boxes and lines of text.
+ */
+use class::Class;
+use color::{color_hex, ModuleColors};
+
+const MARGIN: f64 = 20.0;
+const TIME_WIDTH: f64 = 50.0; // Width of the column showing the time of the rows
+const HEADER_HEIGHT: f64 = 30.0; // Height of the row showing the weekdays
+const DAY_WIDTH: f64 = 150.0;
+const SLOT_HEIGHT: f64 = 24.0; // Height of half an hour
+const FONT_SIZE: f64 = 9.0;
+const LINE_HEIGHT: f64 = 11.0;
+
+const WEEKDAYS: &[&str] = &[
+    "Monday", "Tuesday", "Wednesday", "Thursday", "Friday", "Saturday", "Sunday"
+];
+
+struct Rect {
+    x: f64,
+    y: f64,
+    w: f64,
+    h: f64,
+    fill: String, // CSS3 color name
+    stroke: &'static str
+}
+
+struct Text {
+    x: f64,
+    y: f64, // Baseline
+    bold: bool,
+    content: String
+}
+
+struct Layout {
+    width: f64,
+    height: f64,
+    rects: Vec<Rect>,
+    texts: Vec<Text>
+}
+
+/*
+ * Render the timetable as an SVG image
+ */
+pub fn classes_to_svg(classes: &[Class], colors: &ModuleColors) -> String {
+    let layout = layout(classes, colors);
+    let mut ret = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}\" height=\"{h}\" viewBox=\"0 0 {w} {h}\" font-family=\"sans-serif\" font-size=\"{s}\">\n",
+        w = layout.width, h = layout.height, s = FONT_SIZE
+    );
+    ret.push_str(&format!("<rect width=\"{}\" height=\"{}\" fill=\"white\"/>\n", layout.width, layout.height));
+
+    for r in &layout.rects {
+        ret.push_str(&format!(
+            "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"{}\" stroke=\"{}\"/>\n",
+            r.x, r.y, r.w, r.h, r.fill, r.stroke
+        ));
+    }
+
+    for t in &layout.texts {
+        ret.push_str(&format!(
+            "<text x=\"{}\" y=\"{}\"{}>{}</text>\n",
+            t.x, t.y,
+            if t.bold { " font-weight=\"bold\"" } else { "" },
+            escape_xml(&t.content)
+        ));
+    }
+
+    ret.push_str("</svg>\n");
+    ret
+}
+
+/*
+ * Render the timetable as a single-page PDF
+ * Only the standard Helvetica font is used, which means
+ * characters outside of Latin-1 (e.g. Chinese) are replaced by `?`.
+ * Other accented letters (e.g. `é`) are written in the WinAnsi encoding of the font.
+ */
+pub fn classes_to_pdf(classes: &[Class], colors: &ModuleColors) -> Vec<u8> {
+    let layout = layout(classes, colors);
+
+    // The origin of PDF is at the bottom left corner
+    let mut content = String::new();
+    for r in &layout.rects {
+        content.push_str(&format!(
+            "{} rg {} RG {:.2} {:.2} {:.2} {:.2} re B\n",
+            pdf_color(&r.fill), pdf_color(r.stroke),
+            r.x, layout.height - r.y - r.h, r.w, r.h
+        ));
+    }
+
+    content.push_str("0 0 0 rg\n");
+    for t in &layout.texts {
+        content.push_str(&format!(
+            "BT /{} {} Tf {:.2} {:.2} Td ({}) Tj ET\n",
+            if t.bold { "F2" } else { "F1" }, FONT_SIZE,
+            t.x, layout.height - t.y,
+            escape_pdf(&t.content)
+        ));
+    }
+
+    let objects = [
+        "<< /Type /Catalog /Pages 2 0 R >>".to_string(),
+        "<< /Type /Pages /Kids [3 0 R] /Count 1 >>".to_string(),
+        format!(
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {} {}] /Resources << /Font << /F1 4 0 R /F2 5 0 R >> >> /Contents 6 0 R >>",
+            layout.width, layout.height
+        ),
+        "<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica /Encoding /WinAnsiEncoding >>".to_string(),
+        "<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica-Bold /Encoding /WinAnsiEncoding >>".to_string(),
+        format!("<< /Length {} >>\nstream\n{}endstream", content.len(), content)
+    ];
+
+    let mut ret = "%PDF-1.4\n".to_string();
+    let mut offsets = Vec::new();
+    for (i, obj) in objects.iter().enumerate() {
+        offsets.push(ret.len());
+        ret.push_str(&format!("{} 0 obj\n{}\nendobj\n", i + 1, obj));
+    }
+
+    // The cross-reference table must list the byte offset of every object
+    let xref_offset = ret.len();
+    ret.push_str(&format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1));
+    for offset in offsets {
+        ret.push_str(&format!("{:010} 00000 n \n", offset));
+    }
+    ret.push_str(&format!(
+        "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n",
+        objects.len() + 1, xref_offset
+    ));
+    ret.into_bytes()
+}
+
+/*
+ * Arrange the classes into a grid with one column per weekday
+ * and one row per half an hour.
+ * Classes overlapping each other (e.g. on different weeks) on the same day
+ * share the column of the day side by side, like E-Bridge does.
+ */
+fn layout(classes: &[Class], colors: &ModuleColors) -> Layout {
+    // Always show Monday to Friday, and the weekends only if needed
+    let days = classes.iter().map(|c| c.day + 1).max().unwrap_or(0).clamp(5, 7);
+    // Show whole hours only
+    let first_slot = classes.iter().map(|c| c.start.half_hours()).min().unwrap_or(18) / 2 * 2;
+    let last_slot = classes.iter().map(|c| c.end_half_hours()).max().unwrap_or(36).div_ceil(2) * 2;
+    let slots = last_slot.max(first_slot + 2) - first_slot;
+
+    let mut layout = Layout {
+        width: MARGIN * 2.0 + TIME_WIDTH + DAY_WIDTH * (days as f64),
+        height: MARGIN * 2.0 + HEADER_HEIGHT + SLOT_HEIGHT * (slots as f64),
+        rects: Vec::new(),
+        texts: Vec::new()
+    };
+    let top = MARGIN + HEADER_HEIGHT;
+    let left = MARGIN + TIME_WIDTH;
+
+    // The empty grid
+    for (day, name) in WEEKDAYS.iter().enumerate().take(days) {
+        let x = left + DAY_WIDTH * (day as f64);
+        layout.texts.push(Text {
+            x: x + 4.0,
+            y: MARGIN + HEADER_HEIGHT - 10.0,
+            bold: true,
+            content: name.to_string()
+        });
+        for hour in 0..(slots / 2) {
+            layout.rects.push(Rect {
+                x,
+                y: top + SLOT_HEIGHT * 2.0 * (hour as f64),
+                w: DAY_WIDTH,
+                h: SLOT_HEIGHT * 2.0,
+                fill: "white".to_string(),
+                stroke: "lightgray"
+            });
+        }
+    }
+    for hour in 0..(slots / 2) {
+        layout.texts.push(Text {
+            x: MARGIN,
+            y: top + SLOT_HEIGHT * 2.0 * (hour as f64) + LINE_HEIGHT,
+            bold: false,
+            content: format!("{}:00", first_slot / 2 + hour)
+        });
+    }
+
+    // The classes
+    for day in 0..days {
+        let mut day_classes: Vec<&Class> = classes.iter().filter(|c| c.day == day).collect();
+        day_classes.sort_by_key(|c| c.start.half_hours());
+
+        // Assign each class to the first lane that is free at its start time
+        let mut lane_ends: Vec<usize> = Vec::new();
+        let mut lanes = Vec::new();
+        for class in &day_classes {
+            let lane = match lane_ends.iter().position(|end| *end <= class.start.half_hours()) {
+                Some(lane) => lane,
+                None => {
+                    lane_ends.push(0);
+                    lane_ends.len() - 1
+                }
+            };
+            lane_ends[lane] = class.end_half_hours();
+            lanes.push(lane);
+        }
+
+        let lane_width = DAY_WIDTH / (lane_ends.len().max(1) as f64);
+        for (class, lane) in day_classes.iter().zip(lanes) {
+            let x = left + DAY_WIDTH * (day as f64) + lane_width * (lane as f64);
+            let y = top + SLOT_HEIGHT * ((class.start.half_hours() - first_slot) as f64);
+            let h = SLOT_HEIGHT * (class.len as f64);
+            layout.rects.push(Rect {
+                x, y, w: lane_width, h,
+                fill: colors.get(class.module_code()).to_string(),
+                stroke: "dimgray"
+            });
+
+            let lines = vec![
+                class.title.clone(),
+                class.location.clone(),
+                class.lecturer.clone(),
+                format!("Week: {}", class.week_ranges())
+            ];
+            for (i, line) in lines.into_iter().enumerate() {
+                let baseline = y + LINE_HEIGHT * ((i + 1) as f64);
+                // Drop the lines that do not fit into the box
+                if baseline > y + h - 2.0 {
+                    break;
+                }
+                layout.texts.push(Text {
+                    x: x + 3.0,
+                    y: baseline,
+                    bold: i == 0,
+                    content: truncate(&line, lane_width - 6.0)
+                });
+            }
+        }
+    }
+
+    layout
+}
+
+/*
+ * Cut a line of text so that it fits into `width`
+ * This is only an estimation since we do not know the actual font
+ */
+fn truncate(s: &str, width: f64) -> String {
+    let max_chars = (width / (FONT_SIZE * 0.55)) as usize;
+    if s.chars().count() <= max_chars {
+        return s.to_string();
+    }
+    let mut ret: String = s.chars().take(max_chars.max(1) - 1).collect();
+    ret.push('…');
+    ret
+}
+
+fn escape_xml(s: &str) -> String {
+    s.replace("&", "&amp;")
+        .replace("<", "&lt;")
+        .replace(">", "&gt;")
+        .replace("\"", "&quot;")
+}
+
+fn escape_pdf(s: &str) -> String {
+    s.chars()
+        .map(|c| match c {
+            '(' | ')' | '\\' => format!("\\{}", c),
+            '…' => "\\205".to_string(),
+            c if c.is_ascii() && !c.is_ascii_control() => c.to_string(),
+            // WinAnsi agrees with Latin-1 from here on
+            c if ('\u{a0}'..='\u{ff}').contains(&c) => format!("\\{:o}", c as u32),
+            _ => "?".to_string()
+        })
+        .collect()
+}
+
+// CSS3 color name to the `r g b` components used by PDF
+fn pdf_color(color: &str) -> String {
+    let hex = color_hex(color).unwrap_or("#000000");
+    let component = |i: usize| {
+        u8::from_str_radix(&hex[i..(i + 2)], 16).unwrap_or(0) as f64 / 255.0
+    };
+    format!("{:.3} {:.3} {:.3}", component(1), component(3), component(5))
+}
@@ -0,0 +1,212 @@
+/*
+ * Semester calendars
+ * The actual calendars bundled with the app live in `constants.rs`.
+ */
+use chrono::{Date, Datelike, Duration, FixedOffset, TimeZone, Utc};
+use constants::SEMESTERS;
+use ical::offset_utc8;
+
+// (year, month, day)
+pub type Day = (i32, u32, u32);
+
+#[derive(Debug)]
+pub struct Semester {
+    pub id: &'static str, // Used to select the semester, e.g. on the command line
+    pub name: &'static str,
+    pub start: Day, // The day before the semester starts
+    pub end: Day, // The day the semester ends
+    // Periods during which we will not have any classes, e.g. festivals or reading week
+    // Weeks whose working days are all covered by holidays do not count into weeks in a semester.
+    pub holidays: &'static [Period],
+    pub makeup_days: &'static [MakeupDay],
+    // Other dates worth knowing, e.g. examinations
+    pub milestones: &'static [Period]
+}
+
+/*
+ * A named range of days in the school calendar
+ */
+#[derive(Debug)]
+pub struct Period {
+    pub name: &'static str,
+    pub start: Day,
+    pub end: Day // Inclusive
+}
+
+impl Period {
+    pub fn start_date(&self) -> Date<FixedOffset> {
+        to_date(self.start)
+    }
+
+    pub fn end_date(&self) -> Date<FixedOffset> {
+        to_date(self.end)
+    }
+
+    pub fn contains(&self, date: &Date<FixedOffset>) -> bool {
+        *date >= self.start_date() && *date <= self.end_date()
+    }
+}
+
+/*
+ * A day (usually during a weekend) declared as a working day
+ * to make up for a public holiday. Instead of its own classes,
+ * it runs the classes of `weekday` in `week`.
+ */
+#[derive(Debug)]
+pub struct MakeupDay {
+    pub date: Day,
+    pub weekday: usize, // 0 = Monday, same as `Class::day`
+    pub week: usize // Starting from 1
+}
+
+impl Semester {
+    pub fn start_date(&self) -> Date<FixedOffset> {
+        to_date(self.start)
+    }
+
+    pub fn end_date(&self) -> Date<FixedOffset> {
+        to_date(self.end)
+    }
+
+    pub fn holiday(&self, date: &Date<FixedOffset>) -> Option<&'static Period> {
+        self.holidays.iter().find(|h| h.contains(date))
+    }
+
+    pub fn is_vacation_day(&self, date: &Date<FixedOffset>) -> bool {
+        self.holiday(date).is_some()
+    }
+
+    // Whether Monday to Friday of the week are all holidays
+    pub fn is_vacation_week(&self, monday: &Date<FixedOffset>) -> bool {
+        (0..5).all(|i| self.is_vacation_day(&(*monday + Duration::days(i))))
+    }
+
+    pub fn makeup_day(&self, date: &Date<FixedOffset>) -> Option<&'static MakeupDay> {
+        let day = to_day(date);
+        self.makeup_days.iter().find(|m| m.date == day)
+    }
+}
+
+/*
+ * A week of a semester that counts into the weeks of the semester
+ * Weeks run from Monday to Sunday.
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub struct TeachingWeek {
+    pub week: usize, // Starting from 1
+    pub monday: Date<FixedOffset>
+}
+
+/*
+ * A day of a semester on which classes take place, along with
+ * the weekday and week whose classes take place on it.
+ * These only differ from the date itself on make-up days.
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub struct ClassDay {
+    pub date: Date<FixedOffset>,
+    pub weekday: usize, // 0 = Monday, same as `Class::day`
+    pub week: usize // Starting from 1
+}
+
+impl Semester {
+    // The first day of the semester
+    pub fn first_date(&self) -> Date<FixedOffset> {
+        self.start_date().succ()
+    }
+
+    // The first day of the semester from Monday to Friday
+    pub fn first_working_date(&self) -> Date<FixedOffset> {
+        let first = self.first_date();
+        match first.weekday().num_days_from_monday() {
+            weekday @ 5..=6 => first + Duration::days(7 - weekday as i64),
+            _ => first
+        }
+    }
+
+    /*
+     * The teaching weeks of the semester, skipping vacation weeks
+     * The first week is the one containing the first working day of the semester,
+     * even if the semester does not start on a Monday. A semester starting
+     * on a weekend starts teaching on the Monday after it.
+     */
+    pub fn teaching_weeks(&self) -> Vec<TeachingWeek> {
+        let first = self.first_working_date();
+        let first_monday = first - Duration::days(first.weekday().num_days_from_monday() as i64);
+        let weeks = weeks_between(&first_monday, &self.end_date());
+
+        (0..weeks)
+            .map(|i| first_monday + Duration::weeks(i))
+            .filter(|monday| !self.is_vacation_week(monday))
+            .enumerate()
+            .map(|(i, monday)| TeachingWeek { week: i + 1, monday })
+            .collect()
+    }
+
+    /*
+     * The days of the semester on which classes take place,
+     * i.e. days of teaching weeks that are not holidays, and make-up days
+     */
+    pub fn class_days(&self) -> Vec<ClassDay> {
+        let first = self.first_date();
+        let weeks = self.teaching_weeks();
+        let days = (self.end_date().signed_duration_since(first).num_days() + 1).max(0);
+
+        (0..days)
+            .map(|i| first + Duration::days(i))
+            .filter_map(|date| {
+                // Make-up days run the classes of another day instead of their own
+                if let Some(makeup) = self.makeup_day(&date) {
+                    return Some(ClassDay { date, weekday: makeup.weekday, week: makeup.week });
+                }
+
+                if self.is_vacation_day(&date) {
+                    return None;
+                }
+
+                let weekday = date.weekday().num_days_from_monday() as usize;
+                let monday = date - Duration::days(weekday as i64);
+                weeks.iter()
+                    .find(|w| w.monday == monday)
+                    .map(|w| ClassDay { date, weekday, week: w.week })
+            })
+            .collect()
+    }
+}
+
+// Number of weeks starting from `monday` that overlap with the days until `end`
+fn weeks_between(monday: &Date<FixedOffset>, end: &Date<FixedOffset>) -> i64 {
+    let days = end.signed_duration_since(*monday).num_days();
+    if days < 0 {
+        0
+    } else {
+        days / 7 + 1
+    }
+}
+
+/*
+ * Find a semester by its id
+ */
+pub fn find_semester(id: &str) -> Option<&'static Semester> {
+    SEMESTERS.iter().find(|s| s.id == id)
+}
+
+/*
+ * The semester that is going on now, or the next one if we are on a break.
+ * Falls back to the latest semester we know of.
+ */
+pub fn current_semester() -> &'static Semester {
+    let today = Utc::now().with_timezone(&offset_utc8()).date();
+    SEMESTERS.iter()
+        .filter(|s| s.end_date() >= today)
+        .min_by_key(|s| s.end_date())
+        .unwrap_or_else(|| SEMESTERS.iter().max_by_key(|s| s.end_date()).unwrap())
+}
+
+fn to_date(day: Day) -> Date<FixedOffset> {
+    offset_utc8().ymd(day.0, day.1, day.2)
+}
+
+fn to_day(date: &Date<FixedOffset>) -> Day {
+    (date.year(), date.month(), date.day())
+}
@@ -0,0 +1,117 @@
+/*
+ * The grid of slots an HTML table is laid out in, taking `rowspan`
+ * and `colspan` into account the way browsers do (a simplified
+ * version of "forming a table" in the HTML standard).
+ * Every cell is put into the first slot of its row not taken yet,
+ * left free by cells spanning down from the rows above.
+ */
+use html::Element;
+
+// Far more than any timetable needs (7 days split into a few columns
+// each, and 48 half-hours), so that absurd tables or `colspan`
+// attributes cannot take up memory or time
+const MAX_COLUMNS: usize = 64;
+const MAX_ROWS: usize = 128;
+
+pub struct TableCell<'a> {
+    pub elem: &'a Element,
+    pub row: usize,
+    pub col: usize,
+    pub rowspan: usize,
+    pub colspan: usize
+}
+
+pub struct TableGrid<'a> {
+    // In document order, i.e. sorted by row and then by column
+    pub cells: Vec<TableCell<'a>>,
+    // The indices in `cells` of the cells starting in each row
+    rows: Vec<Vec<usize>>,
+    // The index in `cells` of the cell taking each slot, by row and column
+    slots: Vec<Vec<Option<usize>>>,
+    width: usize
+}
+
+impl<'a> TableGrid<'a> {
+    /*
+     * Lay out the `td` and `th` elements of the given `tr` elements
+     * Cells spanning beyond the last row are cut off there.
+     * Tables larger than any timetable are rejected.
+     */
+    pub fn new(rows: &[&'a Element]) -> Result<TableGrid<'a>, String> {
+        if rows.len() > MAX_ROWS {
+            return Err(format!("The table is too large: more than {} rows", MAX_ROWS));
+        }
+        let mut grid = TableGrid {
+            cells: Vec::new(),
+            rows: vec![Vec::new(); rows.len()],
+            slots: vec![Vec::new(); rows.len()],
+            width: 0
+        };
+
+        for (y, row) in rows.iter().enumerate() {
+            let mut x = 0;
+            for elem in row.children().into_iter().filter(|e| e.name == "td" || e.name == "th") {
+                while grid.slot(y, x).is_some() {
+                    x += 1;
+                }
+
+                let colspan = match elem.attr_usize("colspan") {
+                    Some(0) | None => 1,
+                    Some(n) => n
+                };
+                if colspan > MAX_COLUMNS || x + colspan > MAX_COLUMNS {
+                    return Err(format!("The table is too large: more than {} columns", MAX_COLUMNS));
+                }
+                // `rowspan="0"` spans all the rows left
+                let rowspan = match elem.attr_usize("rowspan") {
+                    Some(0) => rows.len() - y,
+                    None => 1,
+                    Some(n) => n.min(rows.len() - y)
+                };
+
+                let index = grid.cells.len();
+                for slots in &mut grid.slots[y..(y + rowspan)] {
+                    if slots.len() < x + colspan {
+                        slots.resize(x + colspan, None);
+                    }
+                    for slot in &mut slots[x..(x + colspan)] {
+                        // Overlapping cells are an error in the table,
+                        // the slot stays with the cell taking it first
+                        if slot.is_none() {
+                            *slot = Some(index);
+                        }
+                    }
+                }
+                grid.rows[y].push(index);
+                grid.cells.push(TableCell { elem, row: y, col: x, rowspan, colspan });
+                x += colspan;
+                grid.width = grid.width.max(x);
+            }
+        }
+        Ok(grid)
+    }
+
+    fn slot(&self, row: usize, col: usize) -> Option<usize> {
+        self.slots.get(row).and_then(|r| r.get(col).cloned()).and_then(|s| s)
+    }
+
+    // The cell covering a slot of the grid, if any
+    pub fn cell_at(&self, row: usize, col: usize) -> Option<&TableCell<'a>> {
+        self.slot(row, col).map(|i| &self.cells[i])
+    }
+
+    // The cells starting in a row, from left to right
+    pub fn row_cells(&self, row: usize) -> Vec<&TableCell<'a>> {
+        self.rows.get(row)
+            .map(|r| r.iter().map(|&i| &self.cells[i]).collect())
+            .unwrap_or_default()
+    }
+
+    pub fn height(&self) -> usize {
+        self.slots.len()
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+}
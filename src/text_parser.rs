@@ -0,0 +1,248 @@
+/*
+ * Best-effort parser for timetables pasted as plain text,
+ * which some browsers do instead of pasting the HTML table.
+ *
+ * The text of the table has one line per half-hour row, starting with
+ * its time label and with the cells separated by tabs. The lines of a
+ * class cell ("title / lecturer / location / Week: ...") are broken
+ * into lines of their own. What gets lost is the `rowspan` of the
+ * cells, i.e. how long classes are and thus which columns the cells
+ * of the following rows are in. They are guessed from the number of
+ * cells in each row, and warnings are given whenever this is ambiguous.
+ * The lengths of classes are only as precise as the time labels, e.g.
+ * with a label every hour, classes last whole hours.
+ */
+use class::{Class, ClassTime};
+use std::cmp::Reverse;
+use parser::{is_week_line, parse_week_line, weekday_from_header};
+
+struct Row {
+    label: String,
+    time: ClassTime,
+    cells: Vec<String>
+}
+
+// A class whose end has not been reached yet
+struct OpenClass {
+    row: usize,
+    column: usize,
+    class: Class
+}
+
+impl OpenClass {
+    // End the class where the row starting at `end` (in half-hours) starts
+    fn end_at(mut self, end: usize) -> OpenClass {
+        self.class.len = end.saturating_sub(self.class.start.half_hours()).max(1);
+        self
+    }
+}
+
+/*
+ * Parse the text of a timetable
+ * Returns the classes found, along with warnings about anything
+ * that may have been read wrong.
+ */
+pub fn parse_text(text: &str) -> Result<(Vec<Class>, Vec<String>), String> {
+    let text = text.replace('\r', "").replace('\u{a0}', " ");
+    let mut warnings = Vec::new();
+
+    let (header, rows) = split_rows(&text);
+    if rows.is_empty() {
+        return Err("No time labels found".to_string());
+    }
+    // The rows without any class have a cell in every column
+    let columns = rows.iter().map(|r| r.cells.len()).max().unwrap_or(0);
+    if columns == 0 {
+        return Err("No cells found, they should be separated by tabs".to_string());
+    }
+    let days = column_weekdays(&header, columns, &mut warnings);
+    // The time between two labels, which the last row is assumed to last
+    let step = rows.windows(2)
+        .map(|w| w[1].time.half_hours().saturating_sub(w[0].time.half_hours()))
+        .filter(|&d| d > 0)
+        .min()
+        .unwrap_or(1);
+    if step > 1 {
+        // The rows in between have no label to tell them apart
+        warnings.push(format!(
+            "The time labels are {} minutes apart, the classes may start and end at other times",
+            step * 30
+        ));
+    }
+
+    let mut ret: Vec<OpenClass> = Vec::new();
+    let mut open: Vec<OpenClass> = Vec::new();
+    for (r, row) in rows.iter().enumerate() {
+        // Columns without a cell in this row are taken by classes
+        // started in the rows above
+        let covered = columns - row.cells.len();
+        if open.len() > covered {
+            // Nothing tells which columns have no cell in this row.
+            // Assume the classes started first are the first to end, and
+            // among those started together, that the columns without a cell
+            // are the leftmost ones, i.e. the cells are aligned to the right.
+            open.sort_by_key(|o| (Reverse(o.row), o.column));
+            if covered > 0 {
+                warnings.push(format!(
+                    "At {}: could not tell which of {} ended, guessed {}",
+                    row.label, titles(&open), titles(&open[covered..])
+                ));
+            }
+            for o in open.drain(covered..) {
+                ret.push(o.end_at(row.time.half_hours()));
+            }
+        } else if open.len() < covered {
+            warnings.push(format!(
+                "At {}: {} cells are missing, assumed to be empty",
+                row.label, covered - open.len()
+            ));
+        }
+
+        let free: Vec<usize> = (0..columns).filter(|c| !open.iter().any(|o| o.column == *c)).collect();
+        for (cell, &column) in row.cells.iter().zip(&free) {
+            if let Some(class) = parse_cell(cell, days[column], &row.time, &row.label, &mut warnings) {
+                open.push(OpenClass { row: r, column, class });
+            }
+        }
+    }
+    // but no longer than until midnight
+    let end = (rows[rows.len() - 1].time.half_hours() + step).min(48);
+    for o in open {
+        ret.push(o.end_at(end));
+    }
+
+    // In the same order as the HTML parser
+    ret.sort_by_key(|o| (o.row, o.column));
+    Ok((ret.into_iter().map(|o| o.class).collect(), warnings))
+}
+
+/*
+ * Split the text into the part before the first time label,
+ * and the rows of the table, each starting with a time label
+ */
+fn split_rows(text: &str) -> (String, Vec<Row>) {
+    let mut header = String::new();
+    let mut rows: Vec<(String, ClassTime, String)> = Vec::new();
+    for line in text.split('\n') {
+        let mut fields = line.splitn(2, '\t');
+        let first = fields.next().unwrap().trim();
+        match (time_label(first), rows.last_mut()) {
+            (Some(time), _) => rows.push((first.to_string(), time, fields.next().map(|f| format!("\t{}", f)).unwrap_or_default())),
+            (None, Some(&mut (_, _, ref mut cells))) => {
+                cells.push('\n');
+                cells.push_str(line);
+            },
+            (None, None) => {
+                header.push_str(line);
+                header.push('\n');
+            }
+        }
+    }
+
+    let rows = rows.into_iter()
+        .map(|(label, time, cells)| Row {
+            label,
+            time,
+            // Each cell starts with the tab separating it from the previous one
+            cells: cells.split('\t').skip(1).map(|c| c.to_string()).collect()
+        })
+        .collect();
+    (header, rows)
+}
+
+// A time label such as `9:00`, at which a row starts
+fn time_label(s: &str) -> Option<ClassTime> {
+    if !s.contains(':') {
+        return None;
+    }
+    // `24:00` is the end of a day, no row starts then
+    ClassTime::parse(s).filter(|t| t.hour <= 23)
+}
+
+/*
+ * The weekday of each column, from the weekdays named in the header
+ */
+fn column_weekdays(header: &str, columns: usize, warnings: &mut Vec<String>) -> Vec<usize> {
+    // The line naming the most weekdays
+    let mut days: Vec<usize> = header.split('\n')
+        .map(|line| line.split('\t').filter_map(weekday_from_header).collect::<Vec<_>>())
+        .max_by_key(|days| days.len())
+        .unwrap_or_default();
+    if days.is_empty() {
+        warnings.push("No weekdays found above the timetable, assumed to start on Monday".to_string());
+        days = (0..7).collect();
+    }
+
+    if columns > days.len() {
+        // Some days are split into several columns, but which ones cannot be told
+        warnings.push(format!(
+            "There are {} columns but {} weekdays, classes may be put on the wrong day",
+            columns, days.len()
+        ));
+        return (0..columns).map(|c| days[c * days.len() / columns]).collect();
+    }
+    days
+}
+
+/*
+ * Parse a cell, which is either empty or holds a class
+ * format:
+ * >  title
+ * >  lecturers
+ * >  location
+ * >  Week: x-y, z-w, t, ...
+ * The last line may also be in Chinese, see `parser::is_week_line`.
+ */
+fn parse_cell(cell: &str, day: usize, start: &ClassTime, label: &str, warnings: &mut Vec<String>) -> Option<Class> {
+    let lines: Vec<&str> = cell.split('\n')
+        .map(|l| l.trim())
+        .filter(|l| !l.is_empty())
+        .collect();
+    if lines.is_empty() {
+        return None;
+    }
+
+    let week_line = match lines.iter().position(|l| is_week_line(l)) {
+        Some(i) => i,
+        None => {
+            warnings.push(format!("At {}: ignored \"{}\", which does not look like a class", label, lines.join(" ")));
+            return None;
+        }
+    };
+    let weeks = match parse_week_line(lines[week_line]) {
+        Ok(weeks) => weeks,
+        Err(err) => {
+            warnings.push(format!("At {}: ignored \"{}\": {}", label, lines.join(" "), err));
+            return None;
+        }
+    };
+
+    let info = &lines[..week_line];
+    if info.len() != 3 {
+        warnings.push(format!(
+            "At {}: expected the title, lecturer and location before \"{}\", found \"{}\"",
+            label, lines[week_line], info.join(" / ")
+        ));
+    }
+    if info.is_empty() {
+        return None;
+    }
+
+    Some(Class {
+        title: info[0].to_string(),
+        lecturer: if info.len() > 2 { info[1..(info.len() - 1)].join(", ") } else { "".to_string() },
+        location: if info.len() > 1 { info[info.len() - 1].to_string() } else { "".to_string() },
+        day,
+        weeks,
+        start: start.clone(),
+        len: 0, // Set once the end of the class is found
+        owner: None
+    })
+}
+
+fn titles(classes: &[OpenClass]) -> String {
+    classes.iter()
+        .map(|o| o.class.title.as_str())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
@@ -1,5 +1,6 @@
-use stdweb::Value;
-use stdweb::web::{IElement, INode, Element};
+use wasm_bindgen::JsCast;
+use wasm_bindgen::closure::Closure;
+use web_sys::{Document, DragEvent, Element, Event, File, FileReader, HtmlInputElement, HtmlSelectElement};
 
 #[allow(unused_macros)]
 macro_rules! clone {
@@ -36,31 +37,106 @@ macro_rules! clone {
     );
 }
 
-pub trait ToElement {
-    fn to_element(&self) -> Option<Element>;
+pub fn document() -> Document {
+    web_sys::window().unwrap().document().unwrap()
 }
 
-impl<T: INode> ToElement for T {
-    fn to_element(&self) -> Option<Element> {
-        self.as_ref().clone().downcast()
+/*
+ * Call `listener` on every `event` (e.g. `click`) of the element
+ * The listener lives as long as the page does.
+ */
+pub fn add_event_listener<F: FnMut(Event) + 'static>(elem: &Element, event: &str, listener: F) {
+    let closure = Closure::wrap(Box::new(listener) as Box<dyn FnMut(Event)>);
+    elem.add_event_listener_with_callback(event, closure.as_ref().unchecked_ref()).unwrap();
+    closure.forget();
+}
+
+/*
+ * The current value of an `input` or `select` element
+ */
+pub fn get_input_value(input: &Element) -> String {
+    if let Some(input) = input.dyn_ref::<HtmlInputElement>() {
+        input.value()
+    } else if let Some(select) = input.dyn_ref::<HtmlSelectElement>() {
+        select.value()
+    } else {
+        "".to_string()
+    }
+}
+
+/*
+ * Whether a checkbox is checked
+ */
+pub fn is_checked(input: &Element) -> bool {
+    input.dyn_ref::<HtmlInputElement>().is_some_and(|i| i.checked())
+}
+
+/*
+ * Replace the options of a `select` element with `(value, text)` pairs
+ */
+pub fn fill_select(select: &Element, options: &[(&str, &str)], selected: &str) {
+    select.set_text_content(None);
+    for &(value, text) in options {
+        let option = document().create_element("option").unwrap();
+        option.set_attribute("value", value).unwrap();
+        option.set_text_content(Some(text));
+        if value == selected {
+            option.set_attribute("selected", "selected").unwrap();
+        }
+        select.append_child(&option).unwrap();
     }
 }
 
-pub trait ElementAttribute {
-    fn get_attribute(&self, name: &str) -> Value;
-    fn set_attribute(&self, name: &str, value: Value);
+/*
+ * The file selected in an `<input type="file">`, if any
+ */
+pub fn selected_file(input: &Element) -> Option<File> {
+    input.dyn_ref::<HtmlInputElement>()
+        .and_then(|i| i.files())
+        .and_then(|files| files.get(0))
 }
 
-impl<T: IElement> ElementAttribute for T {
-    fn get_attribute(&self, name: &str) -> Value {
-        js!(
-            return @{self.as_ref()}.getAttribute(@{name});
-        )
+/*
+ * Forget the file selected in an `<input type="file">`,
+ * so that selecting the same file again counts as a change
+ */
+pub fn clear_selected_file(input: &Element) {
+    if let Some(input) = input.dyn_ref::<HtmlInputElement>() {
+        input.set_value("");
     }
+}
+
+/*
+ * The first file dropped in a `drop` event, if any
+ */
+pub fn dropped_file(ev: &Event) -> Option<File> {
+    ev.dyn_ref::<DragEvent>()
+        .and_then(|ev| ev.data_transfer())
+        .and_then(|data| data.files())
+        .and_then(|files| files.get(0))
+}
 
-    fn set_attribute(&self, name: &str, value: Value) {
-        js!(
-            @{self.as_ref()}.setAttribute(@{name}, @{value});
-        );
+/*
+ * Read a file as text
+ * The callback receives `None` if the file could not be read.
+ */
+pub fn read_file<F: FnOnce(Option<String>) + 'static>(file: &File, callback: F) {
+    let reader = FileReader::new().unwrap();
+    let _reader = reader.clone();
+    let onloadend = Closure::once_into_js(move || {
+        callback(_reader.result().ok().and_then(|r| r.as_string()));
+    });
+    reader.set_onloadend(Some(onloadend.unchecked_ref()));
+    reader.read_as_text(file).unwrap();
+}
+
+/*
+ * Read the file selected in an `<input type="file">` as text.
+ * The callback receives `None` if no file has been selected.
+ */
+pub fn read_selected_file<F: FnOnce(Option<String>) + 'static>(input: &Element, callback: F) {
+    match selected_file(input) {
+        Some(file) => read_file(&file, callback),
+        None => callback(None)
     }
-}
\ No newline at end of file
+}
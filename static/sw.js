@@ -0,0 +1,47 @@
+/*
+ * Service worker keeping a copy of the whole app,
+ * so that it keeps working without network access.
+ * The version in the name of the cache is filled in by deploy.sh,
+ * so every deployment gets a cache of its own and the old one is dropped.
+ */
+var CACHE = "xjtlu-timetable-{{ver}}";
+var FILES = [
+  "./",
+  "./index.html",
+  "./xjtlu-timetable.js",
+  "./xjtlu-timetable_bg.wasm",
+  "./manifest.webmanifest",
+  "./icon.svg"
+];
+
+self.addEventListener("install", function (event) {
+  event.waitUntil(
+    caches.open(CACHE)
+      .then(function (cache) { return cache.addAll(FILES); })
+      .then(function () { return self.skipWaiting(); })
+  );
+});
+
+self.addEventListener("activate", function (event) {
+  event.waitUntil(
+    caches.keys()
+      .then(function (keys) {
+        return Promise.all(keys
+          .filter(function (key) { return key !== CACHE; })
+          .map(function (key) { return caches.delete(key); }));
+      })
+      .then(function () { return self.clients.claim(); })
+  );
+});
+
+// Serve from the cache first, the files only change with a new service worker
+self.addEventListener("fetch", function (event) {
+  if (event.request.method !== "GET") {
+    return;
+  }
+  event.respondWith(
+    caches.match(event.request, { ignoreSearch: true }).then(function (cached) {
+      return cached || fetch(event.request);
+    })
+  );
+});
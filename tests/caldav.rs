@@ -0,0 +1,146 @@
+/*
+ * Pushing events to a CalDAV calendar, served by a fake server
+ * answering the calendar query with fixed resources
+ */
+#![cfg(feature = "caldav")]
+extern crate chrono;
+extern crate xjtlu_timetable;
+
+use chrono::TimeZone;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpListener;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use xjtlu_timetable::caldav::CalDavClient;
+use xjtlu_timetable::ical::{offset_utc8, ICalBuilder, ICalElement, ICalEvent};
+
+// (method, path, If-Match or If-None-Match, body)
+type Requests = Arc<Mutex<Vec<(String, String, String, String)>>>;
+
+fn event(uid: &str, hour: u32) -> ICalEvent {
+    let start = offset_utc8().ymd(2025, 9, 15).and_hms(hour, 0, 0);
+    ICalEvent {
+        uid: format!("{}@xjtlu-timetable", uid),
+        sequence: 0,
+        summary: format!("{} Lecture", uid),
+        start,
+        end: start + chrono::Duration::hours(1),
+        location: "SA101".to_string(),
+        description: "by Dr. A".to_string(),
+        color: None,
+        categories: Vec::new(),
+        cancelled: false,
+        organizer: None
+    }
+}
+
+fn calendar(events: &[ICalEvent]) -> String {
+    let mut builder = ICalBuilder::new();
+    for e in events {
+        builder.add(e.clone());
+    }
+    builder.serialize()
+}
+
+/*
+ * Serve the resources, given as (name, events), and record the other requests
+ */
+fn serve(resources: Vec<(&str, Vec<ICalEvent>)>) -> (String, Requests) {
+    let mut multistatus = "<?xml version=\"1.0\"?>\n<d:multistatus xmlns:d=\"DAV:\" xmlns:c=\"urn:ietf:params:xml:ns:caldav\">".to_string();
+    for (name, events) in resources {
+        multistatus.push_str(&format!(
+            "<d:response><d:href>/cal/{}.ics</d:href><d:propstat><d:prop><d:getetag>\"{}-etag\"</d:getetag>\
+            <c:calendar-data>{}</c:calendar-data></d:prop></d:propstat></d:response>",
+            name, name, calendar(&events)
+        ));
+    }
+    multistatus.push_str("</d:multistatus>");
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/cal/", listener.local_addr().unwrap());
+    let requests: Requests = Arc::new(Mutex::new(Vec::new()));
+    let recorded = requests.clone();
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut line = String::new();
+            reader.read_line(&mut line).unwrap();
+            let parts: Vec<String> = line.split(' ').map(|s| s.to_string()).collect();
+            let (mut len, mut condition) = (0, String::new());
+            loop {
+                let mut header = String::new();
+                reader.read_line(&mut header).unwrap();
+                if header.trim().is_empty() {
+                    break;
+                }
+                let (name, value) = header.split_at(header.find(':').unwrap());
+                let value = value[1..].trim().to_string();
+                match name.to_ascii_lowercase().as_str() {
+                    "content-length" => len = value.parse().unwrap(),
+                    "if-match" | "if-none-match" => condition = value,
+                    _ => ()
+                }
+            }
+            let mut body = vec![0; len];
+            reader.read_exact(&mut body).unwrap();
+
+            let response = if parts[0] == "REPORT" {
+                format!("HTTP/1.1 207 Multi-Status\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    multistatus.len(), multistatus)
+            } else {
+                recorded.lock().unwrap().push((parts[0].clone(), parts[1].clone(), condition, String::from_utf8(body).unwrap()));
+                "HTTP/1.1 204 No Content\r\nConnection: close\r\n\r\n".to_string()
+            };
+            stream.write_all(response.as_bytes()).unwrap();
+        }
+    });
+    (url, requests)
+}
+
+#[test]
+fn resources_with_several_events_are_written_once() {
+    let foreign = ICalEvent { uid: "party@example.com".to_string(), ..event("Party", 20) };
+    let (url, requests) = serve(vec![
+        ("both", vec![event("CPT101", 9), event("CPT102", 10)]),
+        ("shared", vec![event("CPT103", 11), foreign]),
+        ("alone", vec![event("CPT104", 12)])
+    ]);
+
+    // CPT102, CPT103 and CPT104 are gone, CPT105 is new
+    let report = CalDavClient::new(&url, None, None)
+        .push(vec![event("CPT101", 9), event("CPT105", 14)])
+        .unwrap();
+    assert_eq!(report.to_string(),
+        "1 created, 0 updated, 2 deleted, 1 unchanged, 1 skipped as they share a resource with other events\n");
+
+    let requests = requests.lock().unwrap();
+    let summary: Vec<_> = requests.iter().map(|r| (r.0.as_str(), r.1.as_str(), r.2.as_str())).collect();
+    assert_eq!(summary, vec![
+        ("PUT", "/cal/both.ics", "\"both-etag\""),
+        ("DELETE", "/cal/alone.ics", "\"alone-etag\""),
+        ("PUT", "/cal/CPT105@xjtlu-timetable.ics", "*")
+    ]);
+    // The remaining event of the resource is kept
+    assert!(requests[0].3.contains("UID:CPT101@xjtlu-timetable"));
+    assert!(!requests[0].3.contains("CPT102"));
+}
+
+#[test]
+fn updated_events_keep_the_others_of_their_resource() {
+    let (url, requests) = serve(vec![("both", vec![event("CPT101", 9), event("CPT102", 10)])]);
+
+    let moved = ICalEvent { location: "SA102".to_string(), ..event("CPT101", 9) };
+    let report = CalDavClient::new(&url, None, None)
+        .push(vec![moved, event("CPT102", 10)])
+        .unwrap();
+    assert_eq!(report.to_string(), "0 created, 1 updated, 0 deleted, 1 unchanged\n");
+
+    let requests = requests.lock().unwrap();
+    assert_eq!(requests.len(), 1);
+    assert_eq!((requests[0].0.as_str(), requests[0].1.as_str()), ("PUT", "/cal/both.ics"));
+    let body = requests[0].3.replace("\r\n", "\n");
+    assert!(body.contains("UID:CPT101@xjtlu-timetable\nSEQUENCE:1\n"), "{}", body);
+    assert!(body.contains("LOCATION:SA102"));
+    assert!(body.contains("UID:CPT102@xjtlu-timetable\nSEQUENCE:0\n"));
+}
@@ -0,0 +1,54 @@
+/*
+ * Comparing a previously exported calendar against a new export
+ */
+extern crate xjtlu_timetable;
+
+use xjtlu_timetable::diff::{changes_to_cancel, changes_to_request, diff_events, format_report, has_unknown_uids};
+use xjtlu_timetable::ical::ICalElement;
+use xjtlu_timetable::ical_reader::parse_ical;
+
+fn calendar(events: &[(&str, &str, &str)]) -> String {
+    let mut ret = "BEGIN:VCALENDAR\nVERSION:2.0\n".to_string();
+    for &(uid, summary, start) in events {
+        if !uid.is_empty() {
+            ret.push_str(&format!("BEGIN:VEVENT\nUID:{}\n", uid));
+        } else {
+            ret.push_str("BEGIN:VEVENT\n");
+        }
+        ret.push_str(&format!(
+            "SUMMARY:{}\nDTSTART;TZID=Asia/Shanghai:{}\nDTEND;TZID=Asia/Shanghai:20180910T110000\nEND:VEVENT\n",
+            summary, start
+        ));
+    }
+    ret.push_str("END:VCALENDAR\n");
+    ret
+}
+
+#[test]
+fn updates_name_their_organizer() {
+    let old = parse_ical(&calendar(&[("a@x", "CPT101", "20180910T090000"), ("b@x", "MTH013", "20180910T090000")])).unwrap();
+    let new = parse_ical(&calendar(&[("c@x", "CPT101", "20180910T093000")])).unwrap();
+    let changes = diff_events(&old, &new);
+    assert!(!has_unknown_uids(&changes));
+
+    let request = changes_to_request(&changes).serialize();
+    assert!(request.contains("METHOD:REQUEST\n"));
+    assert!(request.contains("UID:a@x\n"));
+    assert!(request.contains("ORGANIZER:mailto:"));
+
+    let cancel = changes_to_cancel(&changes).serialize();
+    assert!(cancel.contains("METHOD:CANCEL\n"));
+    assert!(cancel.contains("UID:b@x\n"));
+    assert!(cancel.contains("ORGANIZER:mailto:"));
+}
+
+#[test]
+fn calendars_without_uids_cannot_be_updated() {
+    // Exported before events had UIDs
+    let old = parse_ical(&calendar(&[("", "CPT101", "20180910T090000")])).unwrap();
+    assert_eq!(old[0].uid, "");
+    let new = parse_ical(&calendar(&[("c@x", "MTH013", "20180910T090000")])).unwrap();
+    let changes = diff_events(&old, &new);
+    assert!(has_unknown_uids(&changes));
+    assert!(format_report(&changes).contains("without event UIDs"));
+}
@@ -0,0 +1,94 @@
+/*
+ * Finding the common free time of several timetables, during 2025-26 semester 1
+ * Its week 1 starts on Monday 15 Sep, and Sunday 28 Sep is a make-up day
+ * running the Monday classes of week 3.
+ */
+extern crate chrono;
+extern crate xjtlu_timetable;
+
+use chrono::NaiveDate;
+use xjtlu_timetable::class::{Class, ClassTime};
+use xjtlu_timetable::free_slots::{find_free_slots, format_free_slots, FreeSlot, FreeSlotOptions};
+use xjtlu_timetable::semester::find_semester;
+
+fn class(day: usize, start: &str, len: usize) -> Class {
+    Class {
+        title: "CPT101 Lecture".to_string(),
+        lecturer: "Dr. A".to_string(),
+        location: "SA101".to_string(),
+        day,
+        weeks: [true; 14],
+        start: ClassTime::parse(start).unwrap(),
+        len,
+        owner: None
+    }
+}
+
+// The free slots on a day, as `HH:MM-HH:MM`
+fn slots_on(slots: &[FreeSlot], (y, m, d): (i32, u32, u32)) -> Vec<String> {
+    slots.iter()
+        .filter(|s| s.date.naive_local() == NaiveDate::from_ymd(y, m, d))
+        .map(|s| format!("{}-{}", s.start.format("%H:%M"), s.end.format("%H:%M")))
+        .collect()
+}
+
+fn find(timetables: &[Vec<Class>], options: &FreeSlotOptions) -> Vec<FreeSlot> {
+    find_free_slots(timetables, find_semester("2025-26-s1").unwrap(), options)
+}
+
+#[test]
+fn classes_overlapping_the_day() {
+    // Starting before 9:00 and ending after 18:00, in different timetables
+    let timetables = vec![vec![class(0, "8:00", 4)], vec![class(0, "17:00", 4), class(0, "12:00", 2)]];
+    let slots = find(&timetables, &FreeSlotOptions::default());
+    assert_eq!(slots_on(&slots, (2025, 9, 15)), vec!["10:00-12:00", "13:00-17:00"]);
+    assert_eq!(slots[0].week, 1);
+    // Days without classes are free all day
+    assert_eq!(slots_on(&slots, (2025, 9, 16)), vec!["09:00-18:00"]);
+
+    // Busy all day
+    let timetables = vec![vec![class(0, "8:00", 22)]];
+    assert!(slots_on(&find(&timetables, &FreeSlotOptions::default()), (2025, 9, 15)).is_empty());
+}
+
+#[test]
+fn short_slots_are_left_out() {
+    let timetables = vec![vec![class(0, "9:00", 2), class(0, "10:30", 3), class(0, "12:30", 11)]];
+    let slots = find(&timetables, &FreeSlotOptions::default());
+    assert!(slots_on(&slots, (2025, 9, 15)).is_empty());
+
+    let options = FreeSlotOptions { min_len: 1, ..FreeSlotOptions::default() };
+    let slots = find(&timetables, &options);
+    assert_eq!(slots_on(&slots, (2025, 9, 15)), vec!["10:00-10:30", "12:00-12:30"]);
+}
+
+#[test]
+fn slots_until_midnight() {
+    let options = FreeSlotOptions {
+        day_start: ClassTime::parse("20:00").unwrap(),
+        day_end: ClassTime::parse("24:00").unwrap(),
+        ..FreeSlotOptions::default()
+    };
+    let timetables = vec![vec![class(0, "20:00", 2)]];
+    let slots = find(&timetables, &options);
+    assert_eq!(slots_on(&slots, (2025, 9, 15)), vec!["21:00-00:00"]);
+    assert_eq!(slots[0].end.naive_local(), NaiveDate::from_ymd(2025, 9, 16).and_hms(0, 0, 0));
+    assert!(format_free_slots(&slots[..1]).contains("Mon 2025-09-15  21:00-24:00"));
+}
+
+#[test]
+fn weekends_and_makeup_days() {
+    let timetables = vec![vec![class(0, "9:00", 4)]];
+
+    // Make-up days are working days, with the classes of the day they make up for
+    let slots = find(&timetables, &FreeSlotOptions::default());
+    assert_eq!(slots_on(&slots, (2025, 9, 28)), vec!["11:00-18:00"]);
+    assert_eq!(slots.iter().find(|s| s.date.naive_local() == NaiveDate::from_ymd(2025, 9, 28)).unwrap().week, 3);
+    assert!(slots_on(&slots, (2025, 9, 20)).is_empty());
+    assert!(slots_on(&slots, (2025, 9, 21)).is_empty());
+
+    let options = FreeSlotOptions { weekends: true, ..FreeSlotOptions::default() };
+    let slots = find(&timetables, &options);
+    assert_eq!(slots_on(&slots, (2025, 9, 20)), vec!["09:00-18:00"]);
+    assert_eq!(slots_on(&slots, (2025, 9, 28)), vec!["11:00-18:00"]);
+}
@@ -0,0 +1,110 @@
+/*
+ * The HTML parser, on the kinds of markup found in saved E-Bridge pages
+ */
+extern crate xjtlu_timetable;
+
+use xjtlu_timetable::html::{decode_entities, parse_html, Element};
+
+// The names of the children of each element, e.g. `table(tr(td,td),tr(td))`
+fn outline(elem: &Element) -> String {
+    let children: Vec<String> = elem.children().into_iter().map(outline).collect();
+    if children.is_empty() {
+        elem.name.clone()
+    } else {
+        format!("{}({})", elem.name, children.join(","))
+    }
+}
+
+#[test]
+fn table_cells_and_rows_close_implicitly() {
+    let document = parse_html("<table><tr><td>a<td>b<tr><td>c</table>");
+    assert_eq!(outline(&document), "#document(table(tr(td,td),tr(td)))");
+
+    let document = parse_html("<table><thead><tr><th>a<tbody><tr><td>b</table>");
+    assert_eq!(outline(&document), "#document(table(thead(tr(th)),tbody(tr(td))))");
+
+    // A nested table does not close the cell containing it
+    let document = parse_html("<table><tr><td><table><tr><td>a</table><td>b</table>");
+    assert_eq!(outline(&document), "#document(table(tr(td(table(tr(td))),td)))");
+}
+
+#[test]
+fn paragraphs_and_list_items_close_implicitly() {
+    let document = parse_html("<p>one<p>two<ul><li>a<li>b</ul>");
+    assert_eq!(outline(&document), "#document(p,p(ul(li,li)))");
+}
+
+#[test]
+fn unclosed_and_stray_tags() {
+    let document = parse_html("<div><span>text</div></span></p><b>bold");
+    assert_eq!(outline(&document), "#document(div(span),b)");
+    assert_eq!(document.text_content(), "textbold");
+
+    // Void and self-closing elements have no content
+    let document = parse_html("<td>a<br>b<img src=x.png>c<span/>d</td>");
+    assert_eq!(outline(&document), "#document(td(br,img,span))");
+    assert_eq!(document.text_content(), "abcd");
+}
+
+#[test]
+fn attributes() {
+    let document = parse_html("<TD CLASS='gridcell  nonemptycell' rowspan=4 colspan=\"2\" nowrap>x</TD>");
+    let cell = document.select("td", Some("nonemptycell"))[0];
+    assert!(cell.has_class("gridcell"));
+    assert_eq!(cell.attr_usize("rowspan"), Some(4));
+    assert_eq!(cell.attr_usize("colspan"), Some(2));
+    assert_eq!(cell.attr("nowrap"), Some(""));
+    assert_eq!(cell.attr("title"), None);
+}
+
+#[test]
+fn entities() {
+    assert_eq!(decode_entities("A &amp; B &lt;C&gt; &quot;D&quot;"), "A & B <C> \"D\"");
+    assert_eq!(decode_entities("&#20013;&#x6587;&nbsp;"), "中文\u{a0}");
+    // Unknown or unterminated references are kept as they are
+    assert_eq!(decode_entities("&copy; & &amp"), "&copy; & &amp");
+
+    let document = parse_html("<p title=\"a&amp;b\">Week: 1&ndash;3 &amp; 5</p>");
+    assert_eq!(document.select("p", None)[0].attr("title"), Some("a&b"));
+    assert_eq!(document.text_content(), "Week: 1&ndash;3 & 5");
+}
+
+#[test]
+fn raw_text_elements() {
+    let document = parse_html("<script>if (a < b && c) { x = '<td>'; }</script><style>td > p {}</STYLE><td>cell</td>");
+    assert_eq!(outline(&document), "#document(script,style,td)");
+    assert_eq!(document.select("script", None)[0].text_content(), "if (a < b && c) { x = '<td>'; }");
+    assert_eq!(document.select("td", None)[0].text_content(), "cell");
+}
+
+#[test]
+fn comments_and_doctype() {
+    let document = parse_html("<!DOCTYPE html><!-- <td>not a cell</td> --><td>cell</td><?xml?>");
+    assert_eq!(outline(&document), "#document(td)");
+    assert_eq!(document.text_content(), "cell");
+}
+
+#[test]
+fn text_with_less_than_signs_and_multibyte_characters() {
+    let document = parse_html("<td>1 < 2 星期一 <3</td>");
+    assert_eq!(document.text_content(), "1 < 2 星期一 <3");
+}
+
+#[test]
+fn inner_text_of_tables() {
+    let document = parse_html("<table>\n<tr>\n<td>9:00</td>\n<td>a<br>b</td>\n<td></td>\n</tr>\n</table><p>after</p>");
+    assert_eq!(document.inner_text(), "9:00\ta\nb\t\nafter\n");
+}
+
+#[test]
+fn deep_nesting_is_flattened() {
+    // Would overflow the stack when walking or dropping the tree
+    let html = format!("{}<td class=\"coltitle\">9:00</td>", "<div>".repeat(300000));
+    let document = parse_html(&html);
+    assert_eq!(document.text_content(), "9:00");
+    // The elements too deep are left out, but not their content
+    assert_eq!(document.select("div", None).len(), 256);
+    assert!(document.select("td", None).is_empty());
+    assert_eq!(document.inner_text().trim(), "9:00");
+    assert!(xjtlu_timetable::parser::parse_saved_page(&html).is_err());
+}
@@ -0,0 +1,70 @@
+/*
+ * Turning classes into calendar events, during 2025-26 semester 1
+ */
+extern crate xjtlu_timetable;
+
+use xjtlu_timetable::class::{Class, ClassTime};
+use xjtlu_timetable::ical::{classes_to_events, ExportOptions};
+use xjtlu_timetable::semester::find_semester;
+
+fn class(title: &str, location: &str, lecturer: &str) -> Class {
+    let mut weeks = [false; 14];
+    weeks[0] = true;
+    Class {
+        title: title.to_string(),
+        lecturer: lecturer.to_string(),
+        location: location.to_string(),
+        day: 0,
+        weeks,
+        start: ClassTime { hour: 9, half: false },
+        len: 2,
+        owner: None
+    }
+}
+
+fn options() -> ExportOptions {
+    ExportOptions { semester: find_semester("2025-26-s1").unwrap(), ..ExportOptions::default() }
+}
+
+#[test]
+fn uids_are_unique() {
+    // The same title at the same time, told apart by the location or the lecturer
+    let classes = vec![
+        class("CPT101 Lab", "SD546", "Dr. A"),
+        class("CPT101 Lab", "SD554", "Dr. A"),
+        class("CPT101 Lab", "SD546", "Dr. B")
+    ];
+    let events = classes_to_events(&classes, &options());
+    assert_eq!(events.len(), 3);
+    assert_ne!(events[0].uid, events[1].uid);
+    assert_ne!(events[0].uid, events[2].uid);
+    assert_ne!(events[1].uid, events[2].uid);
+
+    // but stay the same across exports
+    assert_eq!(classes_to_events(&classes, &options()), events);
+}
+
+#[test]
+fn sessions_of_several_owners() {
+    let mut alice = vec![class("CPT101 Lecture", "SA101", "Dr. A"), class("MTH013 Tutorial", "SB222", "Dr. C")];
+    let mut bob = vec![class("CPT101 Lecture", "SA101", "Dr. A"), class("MTH013 Tutorial", "SB223", "Dr. C")];
+    for c in &mut alice {
+        c.owner = Some("Alice".to_string());
+    }
+    for c in &mut bob {
+        c.owner = Some("Bob".to_string());
+    }
+    let classes: Vec<Class> = alice.into_iter().chain(bob).collect();
+    let events = classes_to_events(&classes, &options());
+
+    // The lecture they both attend is one event, the tutorials in different rooms are not
+    let summaries: Vec<_> = events.iter().map(|e| (e.summary.as_str(), e.location.as_str())).collect();
+    assert_eq!(summaries, vec![
+        ("CPT101 Lecture (Alice, Bob)", "SA101"),
+        ("MTH013 Tutorial (Alice)", "SB222"),
+        ("MTH013 Tutorial (Bob)", "SB223")
+    ]);
+    assert_eq!(events[0].categories, vec!["CPT101", "Alice", "Bob"]);
+    assert_eq!(events[0].description, "by Dr. A; attended by Alice, Bob");
+    assert_eq!(events[1].categories, vec!["MTH013", "Alice"]);
+}
@@ -0,0 +1,49 @@
+/*
+ * Exporting timetables as JSON and reading them back
+ */
+extern crate xjtlu_timetable;
+
+use xjtlu_timetable::class::{Class, ClassTime};
+use xjtlu_timetable::json::{classes_from_json, classes_to_json, is_json};
+
+fn class(hour: usize, len: usize) -> Class {
+    Class {
+        title: "CPT101 Lecture".to_string(),
+        lecturer: "Dr. A".to_string(),
+        location: "SA101".to_string(),
+        day: 0,
+        weeks: [true; 14],
+        start: ClassTime { hour, half: false },
+        len,
+        owner: None
+    }
+}
+
+#[test]
+fn round_trip() {
+    let mut classes = vec![class(9, 4), class(22, 4)];
+    classes[1].owner = Some("Alice".to_string());
+    classes[1].weeks[5] = false;
+    let json = classes_to_json(&classes);
+    assert!(is_json(&json));
+    assert_eq!(classes_to_json(&classes_from_json(&json).unwrap()), json);
+    // Classes without an owner are written without one
+    assert_eq!(json.matches("owner").count(), 1);
+}
+
+#[test]
+fn invalid_classes_are_rejected() {
+    let invalid = |class: Class| classes_from_json(&classes_to_json(&[class])).unwrap_err();
+    assert_eq!(invalid(class(24, 1)), "Invalid start time of class 0");
+    assert_eq!(invalid(class(9, 0)), "Invalid length of class 0");
+    // Past midnight, or so long it would overflow
+    assert_eq!(invalid(class(22, 5)), "Invalid length of class 0");
+    assert_eq!(invalid(class(9, 1000000000000000)), "Invalid length of class 0");
+    assert_eq!(invalid(class(9, usize::MAX)), "Invalid length of class 0");
+
+    let mut wrong_day = class(9, 2);
+    wrong_day.day = 7;
+    assert_eq!(invalid(wrong_day), "Invalid day of class 0");
+
+    assert!(classes_from_json("[{\"title\": \"CPT101\"}]").unwrap_err().starts_with("Invalid JSON timetable"));
+}
@@ -0,0 +1,69 @@
+/*
+ * Reading timetable pages saved as MHTML
+ */
+extern crate base64;
+extern crate xjtlu_timetable;
+
+use xjtlu_timetable::json::classes_to_json;
+use xjtlu_timetable::mhtml::{decode_quoted_printable, extract_html, is_mhtml};
+use xjtlu_timetable::parser::{parse, parse_saved_page};
+
+const TIMETABLE_EN: &str = include_str!("fixtures/timetable-en.html");
+// The same page saved by Chrome, quoted-printable with soft line breaks
+const TIMETABLE_EN_MHTML: &str = include_str!("fixtures/timetable-en.mhtml");
+
+fn message(part_headers: &str, body: &str) -> String {
+    format!(
+        "MIME-Version: 1.0\r\nContent-Type: multipart/related; boundary=\"b\"\r\n\r\n--b\r\n{}\r\n\r\n{}\r\n--b--\r\n",
+        part_headers, body
+    )
+}
+
+#[test]
+fn saved_page_matches_html() {
+    assert!(is_mhtml(TIMETABLE_EN_MHTML));
+    assert!(!is_mhtml(TIMETABLE_EN));
+    let html = parse(TIMETABLE_EN).unwrap();
+    let mhtml = parse_saved_page(TIMETABLE_EN_MHTML).unwrap();
+    assert_eq!(classes_to_json(&mhtml), classes_to_json(&html));
+}
+
+#[test]
+fn quoted_printable() {
+    assert_eq!(decode_quoted_printable("a=3Db =E9=99=88"), "a=b 陈".as_bytes());
+    // Soft line breaks, also after trailing whitespace
+    assert_eq!(decode_quoted_printable("ab=\r\ncd=\nef= \t\r\ngh"), b"abcdefgh");
+    // Invalid escapes are kept, including at the very end
+    assert_eq!(decode_quoted_printable("a=ZZ"), b"a=ZZ");
+    assert_eq!(decode_quoted_printable("a=4"), b"a=4");
+    assert_eq!(decode_quoted_printable("a="), b"a=");
+    assert_eq!(decode_quoted_printable("a=  "), b"a=  ");
+}
+
+#[test]
+fn base64_body() {
+    let encoded = base64::encode("<p>星期一</p>".as_bytes());
+    let (first, second) = encoded.split_at(8);
+    let content = message("Content-Type: text/html; charset=utf-8\r\nContent-Transfer-Encoding: base64",
+        &format!("{}\r\n{}", first, second));
+    assert_eq!(extract_html(&content).unwrap(), "<p>星期一</p>");
+}
+
+#[test]
+fn folded_headers() {
+    let content = "Content-Type: multipart/related;\r\n\ttype=\"text/html\";\r\n  boundary=\"b\"\r\n\r\n\
+        --b\r\nContent-Type:\r\n text/html\r\nContent-Transfer-Encoding:\r\n\tquoted-printable\r\n\r\n<p>a=3Db</p>\r\n--b--\r\n";
+    assert!(is_mhtml(content));
+    assert_eq!(extract_html(content).unwrap(), "<p>a=b</p>\r\n");
+}
+
+#[test]
+fn first_html_part() {
+    let content = "Content-Type: multipart/related; boundary=b\n\n\
+        --b\nContent-Type: text/css\n\np {}\n--b\nContent-Type: text/html\n\n<p>page</p>\n--b\nContent-Type: text/html\n\n<p>frame</p>\n--b--\n";
+    assert_eq!(extract_html(content).unwrap(), "<p>page</p>\n");
+
+    let no_html = message("Content-Type: text/css", "p {}");
+    assert!(extract_html(&no_html).is_err());
+    assert!(extract_html("Content-Type: multipart/related\r\n\r\n--b--").is_err());
+}
@@ -0,0 +1,116 @@
+/*
+ * Parsing E-Bridge timetable pages, saved in tests/fixtures
+ */
+extern crate xjtlu_timetable;
+
+use xjtlu_timetable::class::Class;
+use xjtlu_timetable::json::classes_to_json;
+use xjtlu_timetable::parser::{parse, parse_week_line, weekday_from_header};
+
+const TIMETABLE_EN: &str = include_str!("fixtures/timetable-en.html");
+const TIMETABLE_ZH: &str = include_str!("fixtures/timetable-zh.html");
+const TIMETABLE_5_DAYS: &str = include_str!("fixtures/timetable-5days.html");
+const TIMETABLE_SPLIT: &str = include_str!("fixtures/timetable-split.html");
+
+fn weeks(list: &[usize]) -> [bool; 14] {
+    let mut ret = [false; 14];
+    for &w in list {
+        ret[w - 1] = true;
+    }
+    ret
+}
+
+// (title, lecturer, location, day, start in half-hours, length in half-hours)
+fn summary(class: &Class) -> (&str, &str, &str, usize, usize, usize) {
+    (&class.title, &class.lecturer, &class.location, class.day, class.start.half_hours(), class.len)
+}
+
+#[test]
+fn english_layout() {
+    let classes = parse(TIMETABLE_EN).unwrap();
+    let summaries: Vec<_> = classes.iter().map(summary).collect();
+    assert_eq!(summaries, vec![
+        ("CPT101 Lecture", "Dr. A", "SA101", 0, 18, 4),
+        ("MTH013 Tutorial", "Dr. C", "SB222", 1, 18, 2),
+        ("CPT103 Lab", "Dr. E, Dr. F", "SD546", 4, 20, 4),
+        ("EAP021 Seminar", "Ms. D", "FB101", 2, 22, 2)
+    ]);
+    assert_eq!(classes[0].weeks, weeks(&[1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13]));
+    assert_eq!(classes[3].weeks, weeks(&[1, 2, 3, 4, 5, 7]));
+}
+
+#[test]
+fn chinese_layout_matches_english() {
+    let en = parse(TIMETABLE_EN).unwrap();
+    let zh = parse(TIMETABLE_ZH).unwrap();
+    assert_eq!(classes_to_json(&zh), classes_to_json(&en));
+}
+
+#[test]
+fn five_day_layout() {
+    let en = parse(TIMETABLE_EN).unwrap();
+    let five_days = parse(TIMETABLE_5_DAYS).unwrap();
+    assert_eq!(classes_to_json(&five_days), classes_to_json(&en));
+}
+
+#[test]
+fn split_columns_and_hourly_labels() {
+    // Monday has two columns, one class spans both of them,
+    // and the time labels span two rows each
+    let classes = parse(TIMETABLE_SPLIT).unwrap();
+    let summaries: Vec<_> = classes.iter().map(summary).collect();
+    assert_eq!(summaries, vec![
+        ("CPT101 Lecture", "Dr. A", "SA101", 0, 18, 4),
+        ("CPT101 Lab", "Dr. B", "SD546", 0, 18, 2),
+        ("EAP021 Seminar", "Ms. D", "FB101", 2, 18, 2),
+        ("MTH013 Tutorial", "Dr. C", "SB222", 1, 20, 3),
+        ("EAP021 Workshop", "Ms. D", "FB102", 2, 21, 2),
+        ("CPT101 Revision", "Dr. A", "SA101", 0, 22, 2)
+    ]);
+    assert_eq!(classes[1].weeks, weeks(&[8, 9, 10, 11, 12, 13, 14]));
+}
+
+#[test]
+fn weekdays_from_header_text() {
+    let en_days = "<td>Monday</td><td>Tuesday</td><td>Wednesday</td><td>Thursday</td><td>Friday</td><td>Saturday</td><td>Sunday</td>";
+    assert!(TIMETABLE_EN.contains(en_days));
+
+    let sunday_first = TIMETABLE_EN.replace(en_days,
+        "<td>Sunday</td><td>Monday</td><td>Tuesday</td><td>Wednesday</td><td>Thursday</td><td>Friday</td><td>Saturday</td>");
+    let days: Vec<_> = parse(&sunday_first).unwrap().iter().map(|c| c.day).collect();
+    assert_eq!(days, vec![6, 0, 3, 1]);
+
+    let unknown = TIMETABLE_EN.replace("<td>Friday</td>", "<td>Holiday</td>");
+    assert!(parse(&unknown).is_err());
+}
+
+#[test]
+fn week_lines() {
+    let expected = weeks(&[1, 2, 3, 9]);
+    for line in &["Week: 1-3, 9", "周: 1-3, 9", "周：1-3，9", "第1-3, 9周", "第1-3周, 第9周", "第1～3、9周"] {
+        assert_eq!(parse_week_line(line), Ok(expected), "{}", line);
+    }
+    assert!(parse_week_line("周: 0-3").is_err());
+}
+
+#[test]
+fn weekday_headers() {
+    assert_eq!(weekday_from_header("Monday"), Some(0));
+    assert_eq!(weekday_from_header("Sun"), Some(6));
+    assert_eq!(weekday_from_header("星期三"), Some(2));
+    assert_eq!(weekday_from_header("星期日"), Some(6));
+    assert_eq!(weekday_from_header("周天"), Some(6));
+    assert_eq!(weekday_from_header("周五"), Some(4));
+    assert_eq!(weekday_from_header("Week"), None);
+    assert_eq!(weekday_from_header("周"), None);
+}
+
+#[test]
+fn times_out_of_the_day() {
+    let late = TIMETABLE_EN.replace("\"coltitle\">9:00<", "\"coltitle\">30:00<");
+    assert_eq!(parse(&late).unwrap_err(), "Invalid hour at row 1");
+
+    // Lasting past midnight
+    let late = TIMETABLE_EN.replace("\"coltitle\">9:00<", "\"coltitle\">23:00<");
+    assert!(parse(&late).unwrap_err().starts_with("Invalid class at 1:"));
+}
@@ -0,0 +1,56 @@
+/*
+ * Questions about a timetable, asked during the first week of 2025-26 semester 1
+ */
+extern crate chrono;
+extern crate xjtlu_timetable;
+
+use chrono::TimeZone;
+use xjtlu_timetable::class::{Class, ClassTime};
+use xjtlu_timetable::ical::offset_utc8;
+use xjtlu_timetable::query::{next_session, sessions_in_week, sessions_on};
+use xjtlu_timetable::semester::find_semester;
+
+fn class(title: &str, day: usize, hour: usize) -> Class {
+    Class {
+        title: title.to_string(),
+        lecturer: "Dr. A".to_string(),
+        location: "SA101".to_string(),
+        day,
+        weeks: [true; 14],
+        start: ClassTime { hour, half: false },
+        len: 2,
+        owner: None
+    }
+}
+
+// Given in a different order than they take place on Monday
+fn timetable() -> Vec<Class> {
+    vec![class("Afternoon", 0, 14), class("Tuesday", 1, 9), class("Morning", 0, 9)]
+}
+
+#[test]
+fn sessions_are_in_order_of_time() {
+    let classes = timetable();
+    let semester = find_semester("2025-26-s1").unwrap();
+
+    let monday = offset_utc8().ymd(2025, 9, 15);
+    let titles: Vec<_> = sessions_on(&classes, semester, &monday).iter().map(|o| o.class.title.as_str()).collect();
+    assert_eq!(titles, vec!["Morning", "Afternoon"]);
+
+    let titles: Vec<_> = sessions_in_week(&classes, semester, 1, None).iter().map(|o| o.class.title.as_str()).collect();
+    assert_eq!(titles, vec!["Morning", "Afternoon", "Tuesday"]);
+}
+
+#[test]
+fn next_session_is_the_earliest_one() {
+    let classes = timetable();
+    let semester = find_semester("2025-26-s1").unwrap();
+
+    let next = |h, m| next_session(&classes, semester, &offset_utc8().ymd(2025, 9, 15).and_hms(h, m, 0))
+        .map(|o| o.class.title.clone());
+    assert_eq!(next(8, 0), Some("Morning".to_string()));
+    // Still going on
+    assert_eq!(next(9, 30), Some("Morning".to_string()));
+    assert_eq!(next(12, 0), Some("Afternoon".to_string()));
+    assert_eq!(next(18, 0), Some("Tuesday".to_string()));
+}
@@ -0,0 +1,85 @@
+/*
+ * Drawing timetables as SVG and PDF
+ */
+extern crate xjtlu_timetable;
+
+use xjtlu_timetable::class::{Class, ClassTime};
+use xjtlu_timetable::color::ModuleColors;
+use xjtlu_timetable::render::{classes_to_pdf, classes_to_svg};
+
+fn class(title: &str, day: usize, start: usize, len: usize) -> Class {
+    Class {
+        title: title.to_string(),
+        lecturer: "Dr. A".to_string(),
+        location: "SA101".to_string(),
+        day,
+        weeks: [true; 14],
+        start: ClassTime::from_half_hours(start),
+        len,
+        owner: None
+    }
+}
+
+// The value of an attribute in a line of SVG
+fn attr(line: &str, name: &str) -> f64 {
+    let start = line.find(&format!(" {}=\"", name)).unwrap() + name.len() + 3;
+    line[start..].split('"').next().unwrap().parse().unwrap()
+}
+
+// (x, width) of the boxes of the classes, which are the ones outlined in dim gray
+fn class_boxes(svg: &str) -> Vec<(f64, f64)> {
+    svg.lines()
+        .filter(|l| l.starts_with("<rect") && l.contains("stroke=\"dimgray\""))
+        .map(|l| (attr(l, "x"), attr(l, "width")))
+        .collect()
+}
+
+#[test]
+fn overlapping_classes_get_lanes() {
+    let colors = ModuleColors::new();
+
+    // 9:00-11:00 and 10:00-11:00 overlap, 11:00-12:00 can take the first lane again
+    let classes = vec![class("CPT101", 0, 18, 4), class("MTH013", 0, 20, 2), class("EAP021", 0, 22, 2)];
+    let boxes = class_boxes(&classes_to_svg(&classes, &colors));
+    assert_eq!(boxes.len(), 3);
+    assert_eq!(boxes[0].1, boxes[1].1);
+    assert!(boxes[0].0 + boxes[0].1 <= boxes[1].0);
+    assert_eq!(boxes[2], boxes[0]);
+    let lane_width = boxes[0].1;
+
+    // Classes one after another, or on other days, have the whole column
+    let classes = vec![class("CPT101", 0, 18, 2), class("MTH013", 0, 20, 2), class("EAP021", 1, 18, 4)];
+    let boxes = class_boxes(&classes_to_svg(&classes, &colors));
+    assert_eq!(boxes[0], boxes[1]);
+    assert_eq!(boxes[0].1, lane_width * 2.0);
+    assert_eq!(boxes[2].1, lane_width * 2.0);
+    assert!(boxes[2].0 >= boxes[0].0 + boxes[0].1);
+}
+
+#[test]
+fn pdf_cross_references() {
+    let classes = vec![class("CPT101 Café (Lab)", 0, 18, 4), class("星期一", 1, 20, 2)];
+    let pdf = classes_to_pdf(&classes, &ModuleColors::new());
+    let text = String::from_utf8(pdf).unwrap();
+    assert!(text.starts_with("%PDF-1.4\n"));
+    assert!(text.ends_with("%%EOF\n"));
+
+    let startxref: usize = text.lines().rev().nth(1).unwrap().parse().unwrap();
+    assert!(text[startxref..].starts_with("xref\n0 7\n"));
+    let entries: Vec<&str> = text[startxref..].lines().skip(3).take(6).collect();
+    for (i, entry) in entries.iter().enumerate() {
+        let offset: usize = entry[..10].parse().unwrap();
+        assert!(text[offset..].starts_with(&format!("{} 0 obj\n", i + 1)), "object {}", i + 1);
+    }
+
+    // The length of the content stream is exact
+    let length_at = text.find("/Length ").unwrap() + 8;
+    let length: usize = text[length_at..].split(' ').next().unwrap().parse().unwrap();
+    let stream_at = text.find("stream\n").unwrap() + 7;
+    assert_eq!(&text[(stream_at + length)..(stream_at + length + 9)], "endstream");
+
+    // Latin-1 is kept, in the encoding of the font, the rest is not
+    assert!(text.contains("(CPT101 Caf\\351 \\(Lab\\)) Tj"));
+    assert!(text.contains("(???) Tj"));
+    assert!(text.is_ascii());
+}
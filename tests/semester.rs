@@ -0,0 +1,221 @@
+/*
+ * Property tests of the occurrence generation over semester calendars
+ */
+extern crate chrono;
+extern crate proptest;
+extern crate xjtlu_timetable;
+
+use chrono::{Datelike, Duration, NaiveDate};
+use proptest::prelude::*;
+use xjtlu_timetable::class::{Class, ClassTime};
+use xjtlu_timetable::color::ModuleColors;
+use xjtlu_timetable::constants::SEMESTERS;
+use xjtlu_timetable::ical::{classes_to_events, ExportOptions};
+use xjtlu_timetable::occurrence::occurrences;
+use xjtlu_timetable::semester::{find_semester, Day, MakeupDay, Period, Semester};
+
+fn to_day(date: NaiveDate) -> Day {
+    (date.year(), date.month(), date.day())
+}
+
+// Any day between 2000 and 2050
+fn arb_date() -> impl Strategy<Value = NaiveDate> {
+    (0i64..(50 * 365)).prop_map(|i| NaiveDate::from_ymd(2000, 1, 1) + Duration::days(i))
+}
+
+/*
+ * A semester starting on any weekday, lasting up to half a year,
+ * with random holidays and make-up days around it.
+ * The calendars live forever since `Semester` only holds static data.
+ */
+fn arb_semester() -> impl Strategy<Value = &'static Semester> {
+    (
+        arb_date(),
+        0i64..180,
+        prop::collection::vec((-10i64..190, 0i64..14), 0..5),
+        prop::collection::vec((-10i64..190, 0usize..7, 0usize..20), 0..4)
+    ).prop_map(|(start, len, holidays, makeups)| {
+        let holidays: Vec<Period> = holidays.into_iter()
+            .map(|(offset, len)| Period {
+                name: "Holiday",
+                start: to_day(start + Duration::days(offset)),
+                end: to_day(start + Duration::days(offset + len))
+            })
+            .collect();
+        let makeup_days: Vec<MakeupDay> = makeups.into_iter()
+            .map(|(offset, weekday, week)| MakeupDay {
+                date: to_day(start + Duration::days(offset)),
+                weekday,
+                week
+            })
+            .collect();
+        let semester = Semester {
+            id: "test",
+            name: "Test",
+            start: to_day(start),
+            end: to_day(start + Duration::days(len)),
+            holidays: Box::leak(holidays.into_boxed_slice()),
+            makeup_days: Box::leak(makeup_days.into_boxed_slice()),
+            milestones: &[]
+        };
+        &*Box::leak(Box::new(semester))
+    })
+}
+
+fn arb_class() -> impl Strategy<Value = Class> {
+    (0usize..7, prop::array::uniform14(any::<bool>()), 8usize..21, any::<bool>(), 1usize..8)
+        .prop_map(|(day, weeks, hour, half, len)| Class {
+            title: "CPT101 Lecture".to_string(),
+            lecturer: "Lecturer".to_string(),
+            location: "SA101".to_string(),
+            day,
+            weeks,
+            start: ClassTime { hour, half },
+            len,
+            owner: None
+        })
+}
+
+proptest! {
+    #[test]
+    fn class_days_are_within_the_semester(semester in arb_semester()) {
+        for day in semester.class_days() {
+            prop_assert!(day.date >= semester.first_date());
+            prop_assert!(day.date <= semester.end_date());
+        }
+    }
+
+    #[test]
+    fn class_days_are_in_order(semester in arb_semester()) {
+        let days = semester.class_days();
+        for pair in days.windows(2) {
+            prop_assert!(pair[0].date < pair[1].date);
+        }
+    }
+
+    #[test]
+    fn no_classes_on_holidays_except_makeup_days(semester in arb_semester()) {
+        for day in semester.class_days() {
+            if semester.makeup_day(&day.date).is_none() {
+                prop_assert!(!semester.is_vacation_day(&day.date));
+                prop_assert_eq!(day.weekday, day.date.weekday().num_days_from_monday() as usize);
+            }
+        }
+    }
+
+    #[test]
+    fn teaching_weeks_are_numbered_consecutively(semester in arb_semester()) {
+        let weeks = semester.teaching_weeks();
+        for (i, week) in weeks.iter().enumerate() {
+            prop_assert_eq!(week.week, i + 1);
+            prop_assert_eq!(week.monday.weekday().num_days_from_monday(), 0);
+            prop_assert!(!semester.is_vacation_week(&week.monday));
+            // Each week overlaps with the semester
+            prop_assert!(week.monday <= semester.end_date());
+            prop_assert!(week.monday + Duration::days(6) >= semester.first_date());
+        }
+    }
+
+    #[test]
+    fn first_week_contains_the_first_working_day(semester in arb_semester()) {
+        let first = semester.first_working_date();
+        prop_assert!(first >= semester.first_date());
+        prop_assert!(first.weekday().num_days_from_monday() < 5);
+        let weeks = semester.teaching_weeks();
+        if let Some(week) = weeks.first() {
+            if first <= semester.end_date()
+                && !semester.is_vacation_week(&(first - Duration::days(first.weekday().num_days_from_monday() as i64))) {
+                prop_assert!(week.monday <= first);
+                prop_assert!(week.monday + Duration::days(6) >= first);
+            }
+        }
+    }
+
+    #[test]
+    fn events_match_their_classes(
+        semester in arb_semester(),
+        classes in prop::collection::vec(arb_class(), 0..6)
+    ) {
+        let options = ExportOptions {
+            semester,
+            colors: ModuleColors::new(),
+            holidays: false,
+            milestones: false,
+            week_numbers: false
+        };
+        let events = classes_to_events(&classes, &options);
+        for event in &events {
+            let date = event.start.date();
+            prop_assert!(date >= semester.first_date());
+            prop_assert!(date <= semester.end_date());
+            prop_assert!(event.end > event.start);
+        }
+
+        // Without holidays and make-up days, a class takes place
+        // exactly once on each of its weeks within the semester.
+        // Identical classes share their events, so they are counted together.
+        if semester.holidays.is_empty() && semester.makeup_days.is_empty() {
+            let days = semester.class_days();
+            for class in &classes {
+                let same_time = |c: &Class| c.day == class.day && c.start.half_hours() == class.start.half_hours() && c.len == class.len;
+                let expected = days.iter()
+                    .filter(|d| d.weekday == class.day)
+                    .filter(|d| classes.iter().any(|c| same_time(c) && c.has_week(d.week)))
+                    .count();
+                let found = events.iter()
+                    .filter(|e| e.start.date().weekday().num_days_from_monday() as usize == class.day)
+                    .filter(|e| e.start.format("%H%M").to_string() == format!("{:02}{}", class.start.hour, if class.start.half { "30" } else { "00" }))
+                    .filter(|e| e.end.signed_duration_since(e.start).num_minutes() == class.len as i64 * 30)
+                    .count();
+                prop_assert_eq!(found, expected);
+            }
+        }
+    }
+
+    #[test]
+    fn occurrences_follow_the_class_days(
+        semester in arb_semester(),
+        classes in prop::collection::vec(arb_class(), 0..6)
+    ) {
+        let days = semester.class_days();
+        let mut last = None;
+        for o in occurrences(&classes, semester) {
+            prop_assert!(days.iter().any(|d| d.date == o.date && d.week == o.week && d.weekday == o.class.day));
+            prop_assert!(o.class.has_week(o.week));
+            prop_assert_eq!(o.start.date(), o.date);
+            prop_assert!(last.is_none_or(|last| last <= o.date));
+            last = Some(o.date);
+        }
+    }
+}
+
+#[test]
+fn semester_starting_on_a_sunday() {
+    // 2017-18 semester 2 starts on Sunday 18 Feb, teaching on the Monday after
+    let semester = find_semester("2017-18-s2").unwrap();
+    let weeks = semester.teaching_weeks();
+    assert_eq!(weeks[0].monday.naive_local(), NaiveDate::from_ymd(2018, 2, 19));
+    assert_eq!(weeks[1].monday.naive_local(), NaiveDate::from_ymd(2018, 2, 26));
+
+    let mut weeks = [false; 14];
+    weeks[0] = true;
+    let class = Class {
+        title: "CPT101 Lecture".to_string(),
+        lecturer: "Lecturer".to_string(),
+        location: "SA101".to_string(),
+        day: 0,
+        weeks,
+        start: ClassTime { hour: 9, half: false },
+        len: 2,
+        owner: None
+    };
+    let dates: Vec<_> = occurrences(&[class], semester).map(|o| o.date.naive_local()).collect();
+    assert_eq!(dates, vec![NaiveDate::from_ymd(2018, 2, 19)]);
+}
+
+#[test]
+fn bundled_semesters_fit_the_weeks_of_classes() {
+    for semester in SEMESTERS {
+        assert!(semester.teaching_weeks().len() <= 14, "{}", semester.id);
+    }
+}
@@ -0,0 +1,110 @@
+/*
+ * Laying out tables with `rowspan` and `colspan`
+ */
+extern crate xjtlu_timetable;
+
+use xjtlu_timetable::html::parse_html;
+use xjtlu_timetable::table_grid::TableGrid;
+
+/*
+ * The text of the cell in each slot, row by row, `.` for empty slots
+ * e.g. `["AB", "AC"]`
+ */
+fn layout(html: &str) -> Vec<String> {
+    let document = parse_html(html);
+    let rows = document.select("tr", None);
+    let grid = TableGrid::new(&rows).unwrap();
+    (0..grid.height())
+        .map(|row| (0..grid.width())
+            .map(|col| grid.cell_at(row, col).map_or(".".to_string(), |c| c.elem.text_content()))
+            .collect())
+        .collect()
+}
+
+#[test]
+fn plain_table() {
+    assert_eq!(layout("<table><tr><td>A<td>B</tr><tr><td>C<td>D</tr></table>"), vec!["AB", "CD"]);
+}
+
+#[test]
+fn rowspan_shifts_later_cells() {
+    let html = "<table>
+        <tr><td rowspan=3>A</td><td rowspan=2>B</td><td>C</td></tr>
+        <tr><td>D</td></tr>
+        <tr><td>E</td><td>F</td></tr>
+    </table>";
+    assert_eq!(layout(html), vec!["ABC", "ABD", "AEF"]);
+}
+
+#[test]
+fn rowspan_and_colspan() {
+    let html = "<table>
+        <tr><td rowspan=2>A</td><td colspan=2>B</td></tr>
+        <tr><td>C</td><td>D</td></tr>
+        <tr><td colspan=2 rowspan=2>E</td><td>F</td></tr>
+        <tr><td>G</td></tr>
+    </table>";
+    assert_eq!(layout(html), vec!["ABB", "ACD", "EEF", "EEG"]);
+}
+
+#[test]
+fn spanning_cell_on_the_right() {
+    // The cell spanning down is not the first of its row
+    let html = "<table>
+        <tr><td>A</td><td>B</td><td rowspan=2>C</td></tr>
+        <tr><td>D</td><td>E</td></tr>
+    </table>";
+    assert_eq!(layout(html), vec!["ABC", "DEC"]);
+}
+
+#[test]
+fn ragged_rows() {
+    let html = "<table><tr><td>A</td></tr><tr><td>B</td><td>C</td><td>D</td></tr></table>";
+    assert_eq!(layout(html), vec!["A..", "BCD"]);
+}
+
+#[test]
+fn rowspan_to_the_end() {
+    // `rowspan="0"` spans all the rows left, too large ones are cut off
+    let html = "<table>
+        <tr><td rowspan=0>A</td><td rowspan=100>B</td></tr>
+        <tr><td>C</td></tr>
+        <tr><td>D</td></tr>
+    </table>";
+    assert_eq!(layout(html), vec!["AB.", "ABC", "ABD"]);
+}
+
+#[test]
+fn overlapping_cells() {
+    // B spans into the slot of A, which keeps it,
+    // and C follows the columns of B all the same
+    let html = "<table>
+        <tr><td>X</td><td rowspan=2>A</td></tr>
+        <tr><td colspan=2>B</td><td>C</td></tr>
+    </table>";
+    assert_eq!(layout(html), vec!["XA.", "BAC"]);
+
+    let document = parse_html(html);
+    let rows = document.select("tr", None);
+    let grid = TableGrid::new(&rows).unwrap();
+    let c = grid.row_cells(1).into_iter().find(|c| c.elem.text_content() == "C").unwrap();
+    assert_eq!((c.row, c.col), (1, 2));
+}
+
+#[test]
+fn absurd_tables_are_rejected() {
+    let wide = format!("<table><tr>{}</tr></table>", "<td colspan=1000>A</td>".repeat(20000));
+    let document = parse_html(&wide);
+    assert!(TableGrid::new(&document.select("tr", None)).is_err());
+
+    let wide = format!("<table><tr><td>A</td><td colspan={}>B</td></tr></table>", usize::MAX);
+    let document = parse_html(&wide);
+    assert!(TableGrid::new(&document.select("tr", None)).is_err());
+
+    let tall = format!("<table>{}</table>", "<tr><td>A</td></tr>".repeat(1000));
+    let document = parse_html(&tall);
+    assert!(TableGrid::new(&document.select("tr", None)).is_err());
+
+    // The limits leave room for any timetable
+    assert_eq!(layout(&format!("<table><tr><td colspan=64>A</td></tr>{}</table>", "<tr><td>B</td></tr>".repeat(100))).len(), 101);
+}
@@ -0,0 +1,88 @@
+/*
+ * Reading timetables pasted as plain text, i.e. the text
+ * a browser copies from the fixtures in tests/fixtures
+ */
+extern crate xjtlu_timetable;
+
+use xjtlu_timetable::class::Class;
+use xjtlu_timetable::html::parse_html;
+use xjtlu_timetable::json::classes_to_json;
+use xjtlu_timetable::parser::parse;
+use xjtlu_timetable::text_parser::parse_text;
+
+const TIMETABLE_EN: &str = include_str!("fixtures/timetable-en.html");
+const TIMETABLE_SPLIT: &str = include_str!("fixtures/timetable-split.html");
+
+// The text of a page, without the table the HTML parser would find
+fn text(html: &str) -> String {
+    parse_html(&html.replace("maintable", "")).inner_text()
+}
+
+// (title, day, start in half-hours, length in half-hours)
+fn summary(class: &Class) -> (&str, usize, usize, usize) {
+    (&class.title, class.day, class.start.half_hours(), class.len)
+}
+
+#[test]
+fn english_layout_matches_html() {
+    let (classes, warnings) = parse_text(&text(TIMETABLE_EN)).unwrap();
+    assert_eq!(classes_to_json(&classes), classes_to_json(&parse(TIMETABLE_EN).unwrap()));
+
+    // Two classes are going on when one of them ends, twice
+    assert_eq!(warnings, vec![
+        "At 10:00: could not tell which of CPT101 Lecture, MTH013 Tutorial ended, guessed MTH013 Tutorial",
+        "At 11:00: could not tell which of CPT103 Lab, CPT101 Lecture ended, guessed CPT101 Lecture"
+    ]);
+}
+
+#[test]
+fn lengths_follow_the_time_labels() {
+    // Labels every hour, without the rows of the half-hours
+    let text = text(TIMETABLE_EN)
+        .replace("9:30\t", "").replace("10:30\t", "").replace("11:30\t", "").replace("12:30\t", "");
+    let lines: Vec<&str> = text.lines().filter(|l| !l.starts_with('\u{a0}') || l.contains("Monday")).collect();
+    let (classes, warnings) = parse_text(&lines.join("\n")).unwrap();
+    let summaries: Vec<_> = classes.iter().map(summary).collect();
+    assert_eq!(summaries, vec![
+        ("CPT101 Lecture", 0, 18, 4),
+        ("MTH013 Tutorial", 1, 18, 2),
+        ("CPT103 Lab", 4, 20, 4),
+        ("EAP021 Seminar", 2, 22, 2)
+    ]);
+    assert!(warnings.contains(&"The time labels are 60 minutes apart, the classes may start and end at other times".to_string()));
+
+    // The last row lasts until the next label would be
+    let (classes, _) = parse_text("\tMonday\n9:00\t\nCPT101 Lecture\nDr. A\nSA101\nWeek: 1-13\n10:00").unwrap();
+    assert_eq!(summary(&classes[0]), ("CPT101 Lecture", 0, 18, 4));
+}
+
+#[test]
+fn split_columns_and_hourly_labels_are_warned_about() {
+    // Neither the split days nor the half-hours without labels survive as text
+    let (classes, warnings) = parse_text(&text(TIMETABLE_SPLIT)).unwrap();
+    let titles: Vec<_> = classes.iter().map(|c| c.title.as_str()).collect();
+    assert_eq!(titles, vec![
+        "CPT101 Lecture", "CPT101 Lab", "EAP021 Seminar", "MTH013 Tutorial", "EAP021 Workshop", "CPT101 Revision"
+    ]);
+    assert!(classes.iter().all(|c| c.len % 2 == 0));
+    assert_eq!(warnings, vec![
+        "There are 4 columns but 3 weekdays, classes may be put on the wrong day",
+        "The time labels are 60 minutes apart, the classes may start and end at other times"
+    ]);
+}
+
+#[test]
+fn not_a_timetable() {
+    assert!(parse_text("Class Timetable\nMonday\tTuesday\n").is_err());
+    assert!(parse_text("9:00\n9:30\n").is_err());
+}
+
+#[test]
+fn classes_end_by_midnight() {
+    // `24:00` does not start a row
+    assert!(parse_text("\tMonday\n24:00\t\nCPT101 Lecture\nDr. A\nSA101\nWeek: 1-13").is_err());
+
+    // The last row lasts as long as the shortest row, but not past midnight
+    let (classes, _) = parse_text("\tMonday\n0:00\t\n23:00\t\nCPT101 Lecture\nDr. A\nSA101\nWeek: 1-13").unwrap();
+    assert_eq!(summary(&classes[0]), ("CPT101 Lecture", 0, 46, 2));
+}